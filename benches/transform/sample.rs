@@ -0,0 +1,108 @@
+use std::{num::NonZeroUsize, time::Duration};
+
+use criterion::{
+    criterion_group, measurement::WallTime, BatchSize, BenchmarkGroup, Criterion, SamplingMode,
+    Throughput,
+};
+use vector::transforms::{
+    sample::{Sample, SampleHashFunction},
+    FunctionTransform, OutputBuffer,
+};
+use vector_core::event::{Event, LogEvent};
+
+struct Payload {
+    sample: Sample,
+    output: OutputBuffer,
+    events: Vec<Event>,
+}
+
+fn setup(
+    total_events: usize,
+    hash_function: SampleHashFunction,
+    decision_cache_size: Option<NonZeroUsize>,
+) -> Payload {
+    let sample = Sample::new(10, Some("user_id".into()), None)
+        .with_hash_function(hash_function)
+        .with_decision_cache_size(decision_cache_size);
+    let output = OutputBuffer::from(Vec::with_capacity(total_events));
+    let events = (0..total_events)
+        .map(|i| {
+            // Each user appears in a burst of consecutive events.
+            let mut log = LogEvent::from("event");
+            log.insert("user_id", format!("user-{:04}", i / 16));
+            Event::Log(log)
+        })
+        .collect();
+    Payload {
+        sample,
+        output,
+        events,
+    }
+}
+
+fn measurement(payload: Payload) {
+    let mut sample = payload.sample;
+    let mut output = payload.output;
+    let events = payload.events;
+
+    for event in events {
+        sample.transform(&mut output, event)
+    }
+}
+
+///
+/// `Sample::transform` benchmarks
+///
+/// This benchmark examines the `transform` of `Sample` over a stream whose key
+/// values repeat in bursts, as is common for per-user events. The `uncached`
+/// cases hash the key value of every event, while the `cached` cases reuse
+/// the bucket of the values seen recently, hashing each value only once.
+///
+/// The transform only enables the cache for SHA-256, but it is measured for
+/// SeaHash too, to show that the lookup costs more than SeaHash itself.
+///
+fn sample(c: &mut Criterion) {
+    let mut group: BenchmarkGroup<WallTime> =
+        c.benchmark_group("vector::transforms::sample::Sample");
+    group.sampling_mode(SamplingMode::Auto);
+
+    let total_events = 1024; // arbitrary constant, the smaller the noisier
+    group.throughput(Throughput::Elements(total_events as u64));
+    for (name, hash_function) in [
+        ("seahash", SampleHashFunction::Seahash),
+        ("sha256", SampleHashFunction::Sha256),
+    ] {
+        group.bench_function(format!("transform/{name}/uncached"), |b| {
+            b.iter_batched(
+                || setup(total_events, hash_function, None),
+                measurement,
+                BatchSize::SmallInput,
+            )
+        });
+        group.bench_function(format!("transform/{name}/cached"), |b| {
+            b.iter_batched(
+                || setup(total_events, hash_function, NonZeroUsize::new(64)),
+                measurement,
+                BatchSize::SmallInput,
+            )
+        });
+    }
+}
+
+criterion_group!(
+    name = benches;
+    config = Criterion::default()
+        .warm_up_time(Duration::from_secs(10))
+        .measurement_time(Duration::from_secs(180))
+        // degree of noise to ignore in measurements, here 1%
+        .noise_threshold(0.01)
+        // likelihood of noise registering as difference, here 5%
+        .significance_level(0.05)
+        // likelihood of capturing the true runtime, here 95%
+        .confidence_level(0.95)
+        // total number of bootstrap resamples, higher is less noisy but slower
+        .nresamples(100_000)
+        // total samples to collect within the set measurement time
+        .sample_size(500);
+    targets = sample
+);
@@ -217,6 +217,55 @@ impl Value {
         }
     }
 
+    /// Returns whether the value is "truthy".
+    ///
+    /// `null`, `false`, `0`, `0.0`, empty strings, empty arrays and empty objects are falsy, all
+    /// other values are truthy.
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Self::Boolean(v) => *v,
+            Self::Bytes(v) => !v.is_empty(),
+            Self::Integer(v) => *v != 0,
+            Self::Float(v) => v.into_inner() != 0.0,
+            Self::Object(v) => !v.is_empty(),
+            Self::Array(v) => !v.is_empty(),
+            Self::Null => false,
+            Self::Regex(_) | Self::Timestamp(_) => true,
+        }
+    }
+
+    /// Returns whether two values are equal, comparing nested arrays and objects element-wise.
+    ///
+    /// Unlike [`PartialEq`], integers and floats are compared by their numeric value, so `1` and
+    /// `1.0` are equal, and floats are compared exactly rather than by their truncated portion.
+    /// Objects are equal when they have the same keys, regardless of insertion order, with equal
+    /// values. `null` is only equal to `null`. All other values are equal if they are of the same
+    /// type and [`PartialEq`] considers them equal.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn deep_eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Integer(a), Self::Integer(b)) => a == b,
+            (Self::Integer(a), Self::Float(b)) | (Self::Float(b), Self::Integer(a)) => {
+                *a as f64 == b.into_inner()
+            }
+            (Self::Float(a), Self::Float(b)) => a.into_inner() == b.into_inner(),
+            (Self::Array(a), Self::Array(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.deep_eq(b))
+            }
+            (Self::Object(a), Self::Object(b)) => {
+                a.len() == b.len()
+                    && a.iter()
+                        .all(|(key, a)| b.get(key).map_or(false, |b| a.deep_eq(b)))
+            }
+            _ => self == other,
+        }
+    }
+
+    /// Returns whether two values are not equal, following the rules of [`Value::deep_eq`].
+    pub fn deep_ne(&self, other: &Self) -> bool {
+        !self.deep_eq(other)
+    }
+
     /// Returns a reference to a field value specified by a path iter.
     #[allow(clippy::needless_pass_by_value)]
     pub fn insert<'a>(
@@ -291,6 +340,58 @@ mod test {
         }
     }
 
+    mod value_deep_eq {
+        use super::*;
+
+        #[test]
+        fn compares_integers_and_floats_numerically() {
+            assert!(Value::Integer(1).deep_eq(&Value::from(1.0)));
+            assert!(Value::from(1.0).deep_eq(&Value::Integer(1)));
+            assert!(Value::Integer(1).deep_ne(&Value::from(1.5)));
+            assert!(Value::from(1.2).deep_ne(&Value::from(1.4)));
+            assert!(Value::from(1.2).deep_eq(&Value::from(1.2)));
+            assert!(Value::Integer(1).deep_ne(&Value::Integer(2)));
+        }
+
+        #[test]
+        fn compares_null() {
+            assert!(Value::Null.deep_eq(&Value::Null));
+            assert!(Value::Null.deep_ne(&Value::Integer(0)));
+            assert!(Value::Null.deep_ne(&Value::from("")));
+            assert!(Value::Boolean(false).deep_ne(&Value::Null));
+        }
+
+        #[test]
+        fn compares_nested_collections() {
+            let a = Value::Array(vec![
+                Value::Integer(1),
+                Value::Array(vec![Value::from(2.0)]),
+            ]);
+            let b = Value::Array(vec![
+                Value::from(1.0),
+                Value::Array(vec![Value::Integer(2)]),
+            ]);
+            assert!(a.deep_eq(&b));
+            assert!(a.deep_ne(&Value::Array(vec![Value::Integer(1)])));
+
+            let a = Value::Object(BTreeMap::from([
+                ("b".to_owned(), Value::Integer(2)),
+                ("a".to_owned(), Value::Array(vec![Value::Null])),
+            ]));
+            let b = Value::Object(BTreeMap::from([
+                ("a".to_owned(), Value::Array(vec![Value::Null])),
+                ("b".to_owned(), Value::from(2.0)),
+            ]));
+            assert!(a.deep_eq(&b));
+
+            let c = Value::Object(BTreeMap::from([
+                ("a".to_owned(), Value::Array(vec![Value::Null])),
+                ("c".to_owned(), Value::Integer(2)),
+            ]));
+            assert!(a.deep_ne(&c));
+        }
+    }
+
     mod value_hash {
         use super::*;
 
@@ -19,6 +19,8 @@ mod tests;
 pub enum ConversionError {
     #[snafu(display("Unknown conversion name {:?}", name))]
     UnknownConversion { name: String },
+    #[snafu(display("Invalid boolean vocabulary {:?}, expected \"TRUE,FALSE\"", vocabulary))]
+    BooleanVocabulary { vocabulary: String },
 }
 
 /// `Conversion` is a place-holder for a type conversion operation, to convert
@@ -27,9 +29,11 @@ pub enum ConversionError {
 #[derive(Clone, Debug)]
 pub enum Conversion {
     Bytes,
+    UrlDecode,
     Integer,
     Float,
     Boolean,
+    BooleanVocabulary { truthy: String, falsy: String },
     Timestamp(TimeZone),
     TimestampFmt(String, TimeZone),
     TimestampTzFmt(String),
@@ -37,6 +41,8 @@ pub enum Conversion {
 
 #[derive(Debug, Eq, PartialEq, Snafu)]
 pub enum Error {
+    #[snafu(display("Invalid percent-encoded string {:?}", s))]
+    UrlDecode { s: String },
     #[snafu(display("Invalid boolean value {:?}", s))]
     BoolParse { s: String },
     #[snafu(display("Invalid integer {:?}: {}", s, source))]
@@ -103,23 +109,29 @@ impl Conversion {
     /// conversion names are supported:
     ///
     ///  * `"asis"`, `"bytes"`, or `"string"` => As-is (no conversion)
+    ///  * `"url_decode"` => Percent-decoded bytes, with `+` decoded as a space
     ///  * `"int"` or `"integer"` => Signed integer
     ///  * `"float"` => Floating point number
     ///  * `"bool"` or `"boolean"` => Boolean
+    ///  * `"bool|TRUE,FALSE"` => Boolean, where `TRUE` and `FALSE` are the
+    ///    only (case-insensitive) strings recognized as `true` and `false`
     ///  * `"timestamp"` => Timestamp, guessed using a set of formats
     ///  * `"timestamp|FORMAT"` => Timestamp using the given format
     ///
     /// # Errors
     ///
-    /// Returns an error if the conversion name is unknown.
+    /// Returns an error if the conversion name is unknown, or if a boolean
+    /// vocabulary is not made of two distinct, non-empty strings.
     pub fn parse(s: impl AsRef<str>, tz: TimeZone) -> Result<Self, ConversionError> {
         let s = s.as_ref();
         let mut split = s.splitn(2, '|').map(str::trim);
         match (split.next(), split.next()) {
             (Some("asis" | "bytes" | "string"), None) => Ok(Self::Bytes),
+            (Some("url_decode"), None) => Ok(Self::UrlDecode),
             (Some("integer" | "int"), None) => Ok(Self::Integer),
             (Some("float"), None) => Ok(Self::Float),
             (Some("bool" | "boolean"), None) => Ok(Self::Boolean),
+            (Some("bool" | "boolean"), Some(vocabulary)) => parse_bool_vocabulary(vocabulary),
             (Some("timestamp"), None) => Ok(Self::Timestamp(tz)),
             (Some("timestamp"), Some(fmt)) => {
                 // DateTime<Utc> can only convert timestamps without
@@ -148,6 +160,7 @@ impl Conversion {
     {
         Ok(match self {
             Self::Bytes => bytes.into(),
+            Self::UrlDecode => url_decode(&bytes)?.into(),
             Self::Integer => {
                 let s = String::from_utf8_lossy(&bytes);
                 s.parse::<i64>()
@@ -163,6 +176,16 @@ impl Conversion {
                 f.into()
             }
             Self::Boolean => parse_bool(&String::from_utf8_lossy(&bytes))?.into(),
+            Self::BooleanVocabulary { truthy, falsy } => {
+                let s = String::from_utf8_lossy(&bytes);
+                if s.eq_ignore_ascii_case(truthy) {
+                    true.into()
+                } else if s.eq_ignore_ascii_case(falsy) {
+                    false.into()
+                } else {
+                    return Err(Error::BoolParse { s: s.into() });
+                }
+            }
             Self::Timestamp(tz) => parse_timestamp(*tz, &String::from_utf8_lossy(&bytes))?.into(),
             Self::TimestampFmt(format, tz) => {
                 let s = String::from_utf8_lossy(&bytes);
@@ -217,6 +240,54 @@ fn parse_bool(s: &str) -> Result<bool, Error> {
     }
 }
 
+/// Decode a percent-encoded string, as found in URL query strings. `+`
+/// decodes to a space, and bytes that are not part of a `%XX` escape are
+/// kept as they are, so already decoded strings are returned unchanged
+/// unless they contain a `%`.
+///
+/// # Errors
+///
+/// Returns an error if a `%` is not followed by two hexadecimal digits.
+fn url_decode(bytes: &[u8]) -> Result<Bytes, Error> {
+    let hex = |byte: u8| char::from(byte).to_digit(16);
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut iter = bytes.iter().copied();
+    while let Some(byte) = iter.next() {
+        match byte {
+            b'+' => decoded.push(b' '),
+            b'%' => match (iter.next().and_then(hex), iter.next().and_then(hex)) {
+                // Both digits are below 16, so the result always fits in a byte.
+                #[allow(clippy::cast_possible_truncation)]
+                (Some(high), Some(low)) => decoded.push((high << 4 | low) as u8),
+                _ => {
+                    return Err(Error::UrlDecode {
+                        s: String::from_utf8_lossy(bytes).into(),
+                    })
+                }
+            },
+            _ => decoded.push(byte),
+        }
+    }
+    Ok(decoded.into())
+}
+
+/// Parse a `"TRUE,FALSE"` boolean vocabulary into a conversion.
+fn parse_bool_vocabulary(vocabulary: &str) -> Result<Conversion, ConversionError> {
+    match vocabulary.split(',').map(str::trim).collect::<Vec<_>>()[..] {
+        [truthy, falsy]
+            if !truthy.is_empty() && !falsy.is_empty() && !truthy.eq_ignore_ascii_case(falsy) =>
+        {
+            Ok(Conversion::BooleanVocabulary {
+                truthy: truthy.into(),
+                falsy: falsy.into(),
+            })
+        }
+        _ => Err(ConversionError::BooleanVocabulary {
+            vocabulary: vocabulary.into(),
+        }),
+    }
+}
+
 /// Does the format specifier have a time zone option?
 fn format_has_zone(fmt: &str) -> bool {
     fmt.contains("%Z")
@@ -2,7 +2,12 @@ use bytes::Bytes;
 use chrono::{DateTime, Utc};
 use ordered_float::NotNan;
 
-use crate::conversion::parse_bool;
+use std::collections::HashMap;
+
+use crate::{
+    conversion::{parse_bool, parse_check_conversion_map, Conversion, Error},
+    datetime::TimeZone,
+};
 
 #[cfg(unix)] // see https://github.com/vectordotdev/vector/issues/1201
 mod unix;
@@ -91,3 +96,90 @@ fn parse_bool_errors() {
     assert!(parse_bool("yes or no").is_err());
     assert!(parse_bool("123.4").is_err());
 }
+
+fn convert_bool(fmt: &str, value: &'static str) -> Result<StubValue, Error> {
+    Conversion::parse(fmt, TimeZone::Local)
+        .unwrap_or_else(|_| panic!("Invalid conversion {fmt:?}"))
+        .convert(value.into())
+}
+
+#[test]
+fn bool_vocabulary_conversion() {
+    for (fmt, truthy, falsy) in [
+        ("bool|Y,N", ["Y", "y"], ["N", "n"]),
+        ("boolean|on,off", ["on", "ON"], ["off", "Off"]),
+        ("bool|1,0", ["1", "1"], ["0", "0"]),
+    ] {
+        for value in truthy {
+            assert_eq!(convert_bool(fmt, value), Ok(StubValue::Boolean(true)));
+        }
+        for value in falsy {
+            assert_eq!(convert_bool(fmt, value), Ok(StubValue::Boolean(false)));
+        }
+    }
+}
+
+#[test]
+fn bool_vocabulary_errors() {
+    assert!(convert_bool("bool|Y,N", "yes").is_err());
+    assert!(convert_bool("bool|on,off", "true").is_err());
+    assert!(convert_bool("bool|1,0", "2").is_err());
+
+    for fmt in ["bool|Y", "bool|Y,N,X", "bool|Y,", "bool|Y,y"] {
+        assert!(Conversion::parse(fmt, TimeZone::Local).is_err(), "{fmt}");
+    }
+}
+
+#[test]
+fn bool_vocabulary_conversion_map() {
+    let types = HashMap::from([
+        ("flag".to_string(), "bool|Y,N".to_string()),
+        ("state".to_string(), "bool|on,off".to_string()),
+    ]);
+    let conversions = parse_check_conversion_map(&types, &["flag", "state"], TimeZone::Local)
+        .expect("valid conversion map");
+
+    assert_eq!(
+        conversions["flag"].convert::<StubValue>("n".into()),
+        Ok(StubValue::Boolean(false))
+    );
+    assert_eq!(
+        conversions["state"].convert::<StubValue>("ON".into()),
+        Ok(StubValue::Boolean(true))
+    );
+}
+
+fn convert_url_decode(value: &'static str) -> Result<StubValue, Error> {
+    Conversion::parse("url_decode", TimeZone::Local)
+        .expect("valid conversion")
+        .convert(value.into())
+}
+
+#[test]
+fn url_decode_conversion() {
+    for (value, want) in [
+        ("hello%20world", "hello world"),
+        ("hello+world", "hello world"),
+        ("a%2Bb%3dc", "a+b=c"),
+        ("caf%C3%A9", "café"),
+        ("hello world", "hello world"),
+        ("", ""),
+    ] {
+        assert_eq!(
+            convert_url_decode(value),
+            Ok(StubValue::Bytes(want.into())),
+            "{value}"
+        );
+    }
+}
+
+#[test]
+fn url_decode_errors() {
+    for value in ["100%", "%2", "%zz", "a%g1b"] {
+        assert_eq!(
+            convert_url_decode(value),
+            Err(Error::UrlDecode { s: value.into() }),
+            "{value}"
+        );
+    }
+}
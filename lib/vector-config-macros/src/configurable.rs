@@ -442,7 +442,12 @@ fn generate_field_metadata(meta_ident: &Ident, field: &Field<'_>) -> proc_macro2
             }
         });
     let maybe_default_value = if field_ty != field_schema_ty {
-        get_metadata_default_value_delegated(meta_ident, field_schema_ty, field.default_value())
+        get_metadata_default_value_delegated(
+            meta_ident,
+            field_ty,
+            field_schema_ty,
+            field.default_value(),
+        )
     } else {
         get_metadata_default_value(meta_ident, field.default_value())
     };
@@ -565,14 +570,18 @@ fn get_metadata_default_value(
 
 fn get_metadata_default_value_delegated(
     meta_ident: &Ident,
+    field_ty: &syn::Type,
     default_ty: &syn::Type,
     default_value: Option<ExprPath>,
 ) -> Option<proc_macro2::TokenStream> {
     default_value.map(|value| {
         let default_ty = get_ty_for_expr_pos(default_ty);
 
+        // The default value is bound to the field type first, as the conversion into the delegated
+        // type cannot infer it when the default value is generic, such as `Default::default`.
         quote! {
-            #meta_ident.set_default_value(#default_ty::from(#value()));
+            let default_value: #field_ty = #value();
+            #meta_ident.set_default_value(#default_ty::from(default_value));
         }
     })
 }
@@ -89,6 +89,34 @@ where
     }
 }
 
+// Smart pointers.
+impl<T> Configurable for Box<T>
+where
+    T: Configurable + Serialize,
+{
+    fn referenceable_name() -> Option<&'static str> {
+        // A box is transparent when (de)serialized, so it shares the schema of `T`.
+        T::referenceable_name()
+    }
+
+    fn is_optional() -> bool {
+        T::is_optional()
+    }
+
+    fn metadata() -> Metadata<Self> {
+        T::metadata().convert()
+    }
+
+    fn validate_metadata(metadata: &Metadata<Self>) -> Result<(), GenerateError> {
+        let converted = metadata.convert::<T>();
+        T::validate_metadata(&converted)
+    }
+
+    fn generate_schema(gen: &mut SchemaGenerator) -> Result<SchemaObject, GenerateError> {
+        T::generate_schema(gen)
+    }
+}
+
 impl Configurable for bool {
     fn generate_schema(_: &mut SchemaGenerator) -> Result<SchemaObject, GenerateError> {
         Ok(generate_bool_schema())
@@ -101,6 +101,13 @@ pub struct GlobalOptions {
     /// a small amount of memory for each metric.
     #[serde(skip_serializing_if = "crate::serde::skip_serializing_if_default")]
     pub expire_metrics_secs: Option<f64>,
+
+    /// The seed mixed into the hashes of all `sample` transforms that do not set their own `seed`.
+    ///
+    /// Changing it shifts which events are selected by all of those transforms at once, such as to
+    /// run reproducible load tests against a different subset of events.
+    #[serde(skip_serializing_if = "crate::serde::skip_serializing_if_default")]
+    pub sample_seed: Option<u64>,
 }
 
 impl GlobalOptions {
@@ -186,6 +193,10 @@ impl GlobalOptions {
             errors.push("conflicting values for 'acknowledgements' found".to_owned());
         }
 
+        if conflicts(&self.sample_seed, &with.sample_seed) {
+            errors.push("conflicting values for 'sample_seed' found".to_owned());
+        }
+
         let data_dir = if self.data_dir.is_none() || self.data_dir == default_data_dir() {
             with.data_dir
         } else if with.data_dir != default_data_dir() && self.data_dir != with.data_dir {
@@ -213,6 +224,7 @@ impl GlobalOptions {
                 proxy: self.proxy.merge(&with.proxy),
                 expire_metrics: self.expire_metrics.or(with.expire_metrics),
                 expire_metrics_secs: self.expire_metrics_secs.or(with.expire_metrics_secs),
+                sample_seed: self.sample_seed.or(with.sample_seed),
             })
         } else {
             Err(errors)
@@ -336,6 +348,20 @@ mod tests {
         assert_eq!(merge(Some(4.0), Some(5.0)), Ok(Some(4.0))); // Uses minimum
     }
 
+    #[test]
+    fn merges_sample_seed() {
+        let merge = |a, b| merge("sample_seed", a, b, |result| result.sample_seed);
+
+        assert_eq!(merge(None, None), Ok(None));
+        assert_eq!(merge(Some(1), None), Ok(Some(1)));
+        assert_eq!(merge(None, Some(2)), Ok(Some(2)));
+        assert_eq!(merge(Some(3), Some(3)), Ok(Some(3)));
+        assert_eq!(
+            merge(Some(4), Some(5)),
+            Err(vec!["conflicting values for 'sample_seed' found".into()])
+        );
+    }
+
     fn merge<P: Debug, T>(
         name: &str,
         dd1: Option<P>,
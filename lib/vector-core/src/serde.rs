@@ -1,6 +1,13 @@
-use std::{fmt, marker::PhantomData};
+use std::{fmt, marker::PhantomData, time::Duration};
 
-use serde::{de, Deserialize, Deserializer};
+use serde::{de, Deserialize, Deserializer, Serializer};
+use serde_with::{DeserializeAs, SerializeAs};
+use vector_config::{
+    schema::generate_string_schema,
+    schemars::{gen::SchemaGenerator, schema::SchemaObject},
+    Configurable, GenerateError, Metadata,
+};
+use vector_config_common::attributes::CustomAttribute;
 
 /// Answers "Is it possible to skip serializing this value, because it's the
 /// default?"
@@ -54,6 +61,106 @@ where
     deserializer.deserialize_any(BoolOrStruct(PhantomData))
 }
 
+/// Handling of `Duration` fields written as duration strings, such as `"5s"` or `"500ms"`, via
+/// `serde_with`s `serde_as` attribute.
+///
+/// Optional fields are handled as well, rather than through `Option<HumanDuration>`, so that their
+/// schema is generated from this helper. They need `#[serde(default)]` to be left unset.
+///
+/// ```rust
+/// # use std::time::Duration;
+/// # use serde::{Deserialize, Serialize};
+/// use serde_with::serde_as;
+/// use vector_core::serde::HumanDuration;
+///
+/// #[serde_as]
+/// #[derive(Deserialize, Serialize)]
+/// struct Foo {
+///    #[serde_as(as = "HumanDuration")]
+///    window: Duration,
+///
+///    #[serde_as(as = "HumanDuration")]
+///    #[serde(default)]
+///    timeout: Option<Duration>,
+/// }
+/// ```
+pub struct HumanDuration;
+
+impl<'de> DeserializeAs<'de, Option<Duration>> for HumanDuration {
+    fn deserialize_as<D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        <Self as DeserializeAs<'de, Duration>>::deserialize_as(deserializer).map(Some)
+    }
+}
+
+impl SerializeAs<Option<Duration>> for HumanDuration {
+    fn serialize_as<S>(source: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match source {
+            Some(duration) => <Self as SerializeAs<Duration>>::serialize_as(duration, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+}
+
+impl<'de> DeserializeAs<'de, Duration> for HumanDuration {
+    fn deserialize_as<D>(deserializer: D) -> Result<Duration, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct HumanDurationVisitor;
+
+        impl<'de> de::Visitor<'de> for HumanDurationVisitor {
+            type Value = Duration;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str(r#"a duration such as "5s" or "500ms""#)
+            }
+
+            fn visit_str<E>(self, value: &str) -> Result<Duration, E>
+            where
+                E: de::Error,
+            {
+                humantime::parse_duration(value)
+                    .map_err(|error| E::custom(format!("invalid duration {value:?}: {error}")))
+            }
+        }
+
+        deserializer.deserialize_str(HumanDurationVisitor)
+    }
+}
+
+impl SerializeAs<Duration> for HumanDuration {
+    fn serialize_as<S>(source: &Duration, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(&humantime::format_duration(*source))
+    }
+}
+
+impl Configurable for HumanDuration {
+    fn referenceable_name() -> Option<&'static str> {
+        Some("vector_core::serde::HumanDuration")
+    }
+
+    fn metadata() -> Metadata<Self> {
+        let mut metadata = Metadata::default();
+        metadata.set_description("A span of time, such as `5s` or `500ms`.");
+        metadata.add_custom_attribute(CustomAttribute::kv("docs::examples", "5s"));
+        metadata.add_custom_attribute(CustomAttribute::kv("docs::examples", "500ms"));
+        metadata
+    }
+
+    fn generate_schema(_: &mut SchemaGenerator) -> Result<SchemaObject, GenerateError> {
+        Ok(generate_string_schema())
+    }
+}
+
 /// Handling of ASCII characters in `u8` fields via `serde`s `with` attribute.
 ///
 /// ```rust
@@ -138,3 +245,59 @@ pub mod ascii_char {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Serialize};
+    use serde_with::serde_as;
+
+    use super::HumanDuration;
+
+    #[serde_as]
+    #[derive(Debug, Deserialize, Serialize)]
+    struct Foo {
+        #[serde_as(as = "HumanDuration")]
+        window: Duration,
+    }
+
+    #[test]
+    fn test_deserialize_human_duration() {
+        let foo = serde_json::from_str::<Foo>(r#"{ "window": "5s" }"#).unwrap();
+        assert_eq!(foo.window, Duration::from_secs(5));
+
+        let foo = serde_json::from_str::<Foo>(r#"{ "window": "1m 500ms" }"#).unwrap();
+        assert_eq!(foo.window, Duration::from_millis(60_500));
+    }
+
+    #[test]
+    fn test_deserialize_human_duration_invalid() {
+        let error = serde_json::from_str::<Foo>(r#"{ "window": "5 parsecs" }"#).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .starts_with(r#"invalid duration "5 parsecs": "#),
+            "{error}"
+        );
+
+        let error = serde_json::from_str::<Foo>(r#"{ "window": 5 }"#).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains(r#"expected a duration such as "5s" or "500ms""#),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn test_serialize_human_duration() {
+        let foo = Foo {
+            window: Duration::from_millis(1500),
+        };
+        assert_eq!(
+            serde_json::to_string(&foo).unwrap(),
+            r#"{"window":"1s 500ms"}"#
+        );
+    }
+}
@@ -2,10 +2,16 @@ use anymap::AnyMap;
 use lookup::OwnedTargetPath;
 use std::collections::BTreeSet;
 
+/// The default maximum nesting depth of the expressions in a program.
+const DEFAULT_MAX_EXPRESSION_DEPTH: usize = 256;
+
 pub struct CompileConfig {
     /// Custom context injected by the external environment
     custom: AnyMap,
     read_only_paths: BTreeSet<ReadOnlyPath>,
+    truthy_negation: bool,
+    constant_negation_warning: bool,
+    max_expression_depth: usize,
 }
 
 impl CompileConfig {
@@ -61,6 +67,43 @@ impl CompileConfig {
         self.read_only_paths
             .insert(ReadOnlyPath { path, recursive });
     }
+
+    /// Whether negation accepts values of any type, negating their truthiness.
+    #[must_use]
+    pub fn truthy_negation(&self) -> bool {
+        self.truthy_negation
+    }
+
+    /// Allows negation of non-boolean values, based on their truthiness. By default, negating a
+    /// non-boolean value results in a compile time error.
+    pub fn set_truthy_negation(&mut self, enabled: bool) {
+        self.truthy_negation = enabled;
+    }
+
+    /// Whether negating a constant expression results in a warning.
+    #[must_use]
+    pub fn constant_negation_warning(&self) -> bool {
+        self.constant_negation_warning
+    }
+
+    /// Enables or disables the warning for negating a constant expression, such as `!true`. The
+    /// warning is disabled by default.
+    pub fn set_constant_negation_warning(&mut self, enabled: bool) {
+        self.constant_negation_warning = enabled;
+    }
+
+    /// The maximum nesting depth of the expressions in a program.
+    #[must_use]
+    pub fn max_expression_depth(&self) -> usize {
+        self.max_expression_depth
+    }
+
+    /// Sets the maximum nesting depth of the expressions in a program. Programs nested deeper,
+    /// such as `!!!true` with a maximum depth of 3, result in a compile time error, rather than
+    /// risking a stack overflow when they are compiled or resolved.
+    pub fn set_max_expression_depth(&mut self, max_depth: usize) {
+        self.max_expression_depth = max_depth;
+    }
 }
 
 impl Default for CompileConfig {
@@ -68,6 +111,9 @@ impl Default for CompileConfig {
         Self {
             custom: AnyMap::new(),
             read_only_paths: BTreeSet::new(),
+            truthy_negation: false,
+            constant_negation_warning: false,
+            max_expression_depth: DEFAULT_MAX_EXPRESSION_DEPTH,
         }
     }
 }
@@ -10,7 +10,7 @@ use crate::{
     expression::{
         assignment, function_call, literal, predicate, query, Abort, Array, Assignment, Block,
         Container, Error, Expr, Expression, FunctionArgument, FunctionCall, Group, IfStatement,
-        Literal, Noop, Not, Object, Op, Predicate, Query, Target, Unary, Variable,
+        Literal, Noop, Not, Object, Op, Predicate, Query, Target, Variable,
     },
     parser::ast::RootExpr,
     program::ProgramInfo,
@@ -51,6 +51,10 @@ pub struct Compiler<'a> {
     /// nullifies the fallibility of that expression.
     fallible_expression_error: Option<Box<dyn DiagnosticMessage>>,
 
+    /// The nesting depth of the expression being compiled, checked against the configured
+    /// maximum so that deeply nested programs are rejected rather than overflowing the stack.
+    depth: usize,
+
     config: CompileConfig,
 }
 
@@ -73,6 +77,7 @@ impl<'a> Compiler<'a> {
             external_assignments: vec![],
             skip_missing_query_target: vec![],
             fallible_expression_error: None,
+            depth: 0,
             config,
         };
         let expressions = compiler.compile_root_exprs(ast, &mut state);
@@ -121,10 +126,18 @@ impl<'a> Compiler<'a> {
             Abort, Assignment, Container, FunctionCall, IfStatement, Literal, Op, Query, Unary,
             Variable,
         };
-        let original_state = state.clone();
-
         let span = node.span();
 
+        let max_depth = self.config.max_expression_depth();
+        if self.depth >= max_depth {
+            self.diagnostics
+                .push(Box::new(Error::TooDeep { span, max_depth }));
+            return None;
+        }
+
+        let original_state = state.clone();
+
+        self.depth += 1;
         let expr = match node.into_inner() {
             Literal(node) => self.compile_literal(node, state),
             Container(node) => self.compile_container(node, state).map(Into::into),
@@ -134,9 +147,11 @@ impl<'a> Compiler<'a> {
             Query(node) => self.compile_query(node, state).map(Into::into),
             FunctionCall(node) => self.compile_function_call(node, state).map(Into::into),
             Variable(node) => self.compile_variable(node, state).map(Into::into),
-            Unary(node) => self.compile_unary(node, state).map(Into::into),
+            Unary(node) => self.compile_unary(node, state),
             Abort(node) => self.compile_abort(node, state).map(Into::into),
-        }?;
+        };
+        self.depth -= 1;
+        let expr = expr?;
 
         // If the previously compiled expression is fallible, _and_ we are
         // currently not tracking any existing fallible expression in the chain
@@ -318,7 +333,22 @@ impl<'a> Compiler<'a> {
 
         let else_block = if let Some(else_node) = else_node {
             *state = after_predicate_state;
-            Some(self.compile_block(else_node, state)?)
+
+            // An `else if` continues the chain rather than nesting within it, so it is compiled at
+            // the depth of this if-statement, allowing long chains of branches.
+            let is_else_if = matches!(
+                &else_node.inner().0[..],
+                [expr] if matches!(expr.inner(), ast::Expr::IfStatement(_))
+            );
+            if is_else_if {
+                self.depth -= 1;
+            }
+            let else_block = self.compile_block(else_node, state);
+            if is_else_if {
+                self.depth += 1;
+            }
+
+            Some(else_block?)
         } else {
             None
         };
@@ -373,29 +403,73 @@ impl<'a> Compiler<'a> {
 
         let original_state = state.clone();
 
-        let op = node.into_inner();
-        let ast::Op(lhs, opcode, rhs) = op;
+        // A chain of operations such as `a || b || c` nests in the left-hand side of each
+        // operation. The chain is compiled in a loop rather than recursively, so that long chains
+        // neither count toward the maximum depth nor overflow the stack.
+        let span = node.span();
+        let ast::Op(mut lhs, opcode, rhs) = node.into_inner();
+        let mut chain = vec![(span, opcode, rhs)];
+        while matches!(lhs.inner(), ast::Expr::Op(_)) {
+            let span = lhs.span();
+            let ast::Op(inner_lhs, opcode, rhs) = match lhs.into_inner() {
+                ast::Expr::Op(op) => op.into_inner(),
+                _ => unreachable!("checked above"),
+            };
+            chain.push((span, opcode, rhs));
+            lhs = inner_lhs;
+        }
 
-        let lhs_span = lhs.span();
-        let lhs = Node::new(lhs_span, self.compile_expr(*lhs, state)?);
+        let mut lhs_span = lhs.span();
+        let mut lhs = self.compile_expr(*lhs, state)?;
+        while let Some((span, opcode, rhs)) = chain.pop() {
+            // If we're using error-coalescing, we need to negate any tracked
+            // fallibility error state for the lhs expression.
+            if opcode.inner() == &Opcode::Err {
+                self.fallible_expression_error = None;
+            }
 
-        // If we're using error-coalescing, we need to negate any tracked
-        // fallibility error state for the lhs expression.
-        if opcode.inner() == &Opcode::Err {
-            self.fallible_expression_error = None;
-        }
+            let rhs_span = rhs.span();
+            let rhs = Node::new(rhs_span, self.compile_operand(*rhs, state)?);
 
-        let rhs_span = rhs.span();
-        let rhs = Node::new(rhs_span, self.compile_expr(*rhs, state)?);
+            let op = Op::new(Node::new(lhs_span, lhs), opcode, rhs, state)
+                .map_err(|err| self.diagnostics.push(Box::new(err)))
+                .ok()?;
+
+            // Both "lhs" and "rhs" are compiled above, but "rhs" isn't always executed.
+            // The expression can provide a more accurate type state.
+            let type_info = op.type_info(&original_state);
+            *state = type_info.state;
+            if chain.is_empty() {
+                return Some(op);
+            }
 
-        let op = Op::new(lhs, opcode, rhs, state)
-            .map_err(|err| self.diagnostics.push(Box::new(err)))
-            .ok()?;
+            // The operations within the chain are not compiled by `compile_expr`, so their
+            // fallibility is tracked here instead.
+            if type_info.result.is_fallible() && self.fallible_expression_error.is_none() {
+                let error = crate::expression::Error::Fallible { span };
+                self.fallible_expression_error = Some(Box::new(error) as _);
+            }
+            lhs_span = span;
+            lhs = op.into();
+        }
 
-        // Both "lhs" and "rhs" are compiled above, but "rhs" isn't always executed.
-        // The expression can provide a more accurate type state.
-        *state = op.type_info(&original_state).state;
-        Some(op)
+        unreachable!("the chain holds at least one operation")
+    }
+
+    /// A right-hand side operand that is itself an operation, such as `b && c` in `a || b && c`,
+    /// continues the chain rather than nesting within it, so it is compiled at the depth of this
+    /// operation.
+    #[cfg(feature = "expr-op")]
+    fn compile_operand(&mut self, node: Node<ast::Expr>, state: &mut TypeState) -> Option<Expr> {
+        let is_chained = matches!(node.inner(), ast::Expr::Op(_));
+        if is_chained {
+            self.depth -= 1;
+        }
+        let expr = self.compile_expr(node, state);
+        if is_chained {
+            self.depth += 1;
+        }
+        expr
     }
 
     #[cfg(not(feature = "expr-op"))]
@@ -847,14 +921,14 @@ impl<'a> Compiler<'a> {
     }
 
     #[cfg(feature = "expr-unary")]
-    fn compile_unary(&mut self, node: Node<ast::Unary>, state: &mut TypeState) -> Option<Unary> {
+    fn compile_unary(&mut self, node: Node<ast::Unary>, state: &mut TypeState) -> Option<Expr> {
         use ast::Unary::Not;
 
-        let variant = match node.into_inner() {
-            Not(node) => self.compile_not(node, state)?.into(),
-        };
-
-        Some(Unary::new(variant))
+        match node.into_inner() {
+            Not(node) => self
+                .compile_not(node, state)
+                .map(crate::expression::Not::into_expr),
+        }
     }
 
     #[cfg(not(feature = "expr-unary"))]
@@ -872,11 +946,29 @@ impl<'a> Compiler<'a> {
     fn compile_not(&mut self, node: Node<ast::Not>, state: &mut TypeState) -> Option<Not> {
         let (not, expr) = node.into_inner().take();
 
-        let node = Node::new(expr.span(), self.compile_expr(*expr, state)?);
+        let not_span = not.span();
+        let expr_span = expr.span();
+        let node = Node::new(expr_span, self.compile_expr(*expr, state)?);
 
-        Not::new(node, not.span(), state)
-            .map_err(|err| self.diagnostics.push(Box::new(err)))
-            .ok()
+        let not = if self.config.truthy_negation() {
+            Not::new_truthy(node)
+        } else {
+            // The negation is kept even if it fails to type check, so that errors in the rest of
+            // the program are reported as well.
+            let (not, error) = Not::new_recoverable(node, not_span, state);
+            if let Some(error) = error {
+                self.diagnostics.push(Box::new(error));
+            }
+            not
+        };
+
+        if self.config.constant_negation_warning() {
+            if let Some(warning) = not.constant_negation(not_span, expr_span) {
+                self.diagnostics.push(Box::new(warning));
+            }
+        }
+
+        Some(not)
     }
 
     #[cfg(feature = "expr-abort")]
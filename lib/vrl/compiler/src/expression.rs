@@ -487,20 +487,24 @@ pub enum Error {
 
     #[error("expression type unavailable")]
     Missing { span: Span, feature: &'static str },
+
+    #[error("expression nested too deeply")]
+    TooDeep { span: Span, max_depth: usize },
 }
 
 impl DiagnosticMessage for Error {
     fn code(&self) -> usize {
-        use Error::{Fallible, Missing};
+        use Error::{Fallible, Missing, TooDeep};
 
         match self {
             Fallible { .. } => 100,
+            TooDeep { .. } => 112,
             Missing { .. } => 900,
         }
     }
 
     fn labels(&self) -> Vec<Label> {
-        use Error::{Fallible, Missing};
+        use Error::{Fallible, Missing, TooDeep};
 
         match self {
             Fallible { span } => vec![
@@ -514,14 +518,21 @@ impl DiagnosticMessage for Error {
                     span,
                 ),
             ],
+            TooDeep { span, max_depth } => vec![
+                Label::primary("this expression is nested too deeply", span),
+                Label::context(
+                    format!("expressions can be nested at most {max_depth} levels deep"),
+                    span,
+                ),
+            ],
         }
     }
 
     fn notes(&self) -> Vec<Note> {
-        use Error::{Fallible, Missing};
+        use Error::{Fallible, Missing, TooDeep};
 
         match self {
-            Fallible { .. } => vec![Note::SeeErrorDocs],
+            Fallible { .. } | TooDeep { .. } => vec![Note::SeeErrorDocs],
             Missing { .. } => vec![],
         }
     }
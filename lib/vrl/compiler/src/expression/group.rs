@@ -17,6 +17,11 @@ impl Group {
             inner: Box::new(inner),
         }
     }
+
+    /// Returns the expression inside the parentheses.
+    pub(crate) fn inner(&self) -> &Expr {
+        &self.inner
+    }
 }
 
 impl Expression for Group {
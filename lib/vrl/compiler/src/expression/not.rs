@@ -1,10 +1,11 @@
 use std::fmt;
 
-use diagnostic::{DiagnosticMessage, Label, Note, Urls};
+use diagnostic::{DiagnosticMessage, Label, Note, Severity, Urls};
+use value::Value;
 
 use crate::state::{TypeInfo, TypeState};
 use crate::{
-    expression::{Expr, Resolved},
+    expression::{Container, Expr, Resolved, Unary, Variant},
     parser::Node,
     value::{Kind, VrlValueConvert},
     Context, Expression, Span, TypeDef,
@@ -15,30 +16,139 @@ pub(crate) type Result = std::result::Result<Not, Error>;
 #[derive(Debug, Clone, PartialEq)]
 pub struct Not {
     inner: Box<Expr>,
+    truthy: bool,
 }
 
 impl Not {
     pub fn new(node: Node<Expr>, not_span: Span, state: &TypeState) -> Result {
+        match Self::new_recoverable(node, not_span, state) {
+            (not, None) => Ok(not),
+            (_, Some(error)) => Err(error),
+        }
+    }
+
+    /// Creates a negation like [`Not::new`], but returns the negation even if the type check
+    /// fails, alongside the error.
+    ///
+    /// This lets the compiler report the error and carry on checking the rest of the program,
+    /// rather than stopping at the first non-boolean negation. The returned negation must not be
+    /// resolved if an error is returned.
+    pub fn new_recoverable(
+        node: Node<Expr>,
+        not_span: Span,
+        state: &TypeState,
+    ) -> (Self, Option<Error>) {
         let (expr_span, expr) = node.take();
         let type_def = expr.type_info(state).result;
 
-        if !type_def.is_boolean() {
-            return Err(Error {
-                variant: ErrorVariant::NonBoolean(type_def.into()),
-                not_span,
-                expr_span,
-            });
-        }
+        let error = (!type_def.is_boolean()).then(|| Error {
+            expr_fallible: type_def.is_fallible(),
+            variant: ErrorVariant::NonBoolean(type_def.into()),
+            not_span,
+            expr_span,
+        });
 
-        Ok(Self {
+        let not = Self {
             inner: Box::new(expr),
+            truthy: false,
+        };
+
+        (not, error)
+    }
+
+    /// Creates a negation of an expression that was not parsed from source, such as one built by a
+    /// rewrite pass or a test.
+    ///
+    /// This performs the same type check as [`Not::new`], but reports errors at an empty span.
+    pub fn from_expr(inner: Expr, state: &TypeState) -> Result {
+        Self::new(Node::new(Span::default(), inner), Span::default(), state)
+    }
+
+    /// Creates a negation that accepts an expression of any type, and negates the truthiness of
+    /// its value instead of requiring a boolean.
+    pub fn new_truthy(node: Node<Expr>) -> Self {
+        Self {
+            inner: Box::new(node.into_inner()),
+            truthy: true,
+        }
+    }
+
+    /// Returns the expression being negated.
+    #[must_use]
+    pub fn inner(&self) -> &Expr {
+        &self.inner
+    }
+
+    /// Returns the expression being negated, for passes that rewrite it in place.
+    #[must_use]
+    pub fn inner_mut(&mut self) -> &mut Expr {
+        &mut self.inner
+    }
+
+    /// Returns a warning if the negated expression is a constant, unless that constant is itself a
+    /// negation, which was already warned about. Parentheses around the constant are looked
+    /// through here only, so other uses of constant values are unaffected.
+    pub(crate) fn constant_negation(
+        &self,
+        not_span: Span,
+        expr_span: Span,
+    ) -> Option<ConstantNegation> {
+        if matches!(self.ungrouped_inner(), Expr::Unary(_)) {
+            return None;
+        }
+
+        let inner = self.ungrouped_inner();
+        inner.as_value().map(|value| ConstantNegation {
+            value,
+            not_span,
+            expr_span,
         })
     }
+
+    /// Returns the expression being negated, without any enclosing parentheses.
+    fn ungrouped_inner(&self) -> &Expr {
+        let mut inner = self.inner.as_ref();
+        while let Expr::Container(Container {
+            variant: Variant::Group(group),
+        }) = inner
+        {
+            inner = group.inner();
+        }
+        inner
+    }
+
+    /// Converts the negation into an expression, rewriting a negated comparison such as
+    /// `!(.a > .b)` into the equivalent `.a <= .b`, and a negated range check such as
+    /// `!(.x >= 1 && .x <= 5)` into the equivalent `.x < 1 || .x > 5`, which resolve without
+    /// evaluating the negation.
+    ///
+    /// See [`Op::negate_comparison`](crate::expression::Op::negate_comparison) and
+    /// [`Op::negate_range_check`](crate::expression::Op::negate_range_check) for which
+    /// expressions are rewritten.
+    pub(crate) fn into_expr(self) -> Expr {
+        let rewritten = match self.ungrouped_inner() {
+            Expr::Op(op) if !self.truthy => {
+                op.negate_comparison().or_else(|| op.negate_range_check())
+            }
+            _ => None,
+        };
+
+        match rewritten {
+            Some(op) => op.into(),
+            None => Unary::new(self.into()).into(),
+        }
+    }
 }
 
 impl Expression for Not {
     fn resolve(&self, ctx: &mut Context) -> Resolved {
-        Ok((!self.inner.resolve(ctx)?.try_boolean()?).into())
+        let value = self.inner.resolve(ctx)?;
+
+        if self.truthy {
+            Ok((!value.is_truthy()).into())
+        } else {
+            Ok((!value.try_boolean()?).into())
+        }
     }
 
     fn type_info(&self, state: &TypeState) -> TypeInfo {
@@ -65,6 +175,10 @@ pub struct Error {
 
     not_span: Span,
     expr_span: Span,
+
+    /// Whether the negated expression can itself fail at runtime, in which case the expression is
+    /// the place to fix the program, rather than the negation.
+    expr_fallible: bool,
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -98,6 +212,20 @@ impl DiagnosticMessage for Error {
         use ErrorVariant::NonBoolean;
 
         match &self.variant {
+            NonBoolean(kind) if self.expr_fallible => vec![
+                Label::primary(
+                    "this expression can result in runtime error",
+                    self.expr_span,
+                ),
+                Label::context(
+                    "handle the error case to ensure runtime success",
+                    self.expr_span,
+                ),
+                Label::context(
+                    format!("negation only works on boolean values, not {kind}"),
+                    self.not_span,
+                ),
+            ],
             NonBoolean(kind) => vec![
                 Label::primary("negation only works on boolean values", self.not_span),
                 Label::context(
@@ -124,3 +252,342 @@ impl DiagnosticMessage for Error {
         }
     }
 }
+
+// -----------------------------------------------------------------------------
+
+/// A warning that the negated expression is a constant, so the negation always resolves to the same
+/// value. This is usually a mistake, or a leftover from debugging.
+#[derive(Debug)]
+pub(crate) struct ConstantNegation {
+    value: Value,
+    not_span: Span,
+    expr_span: Span,
+}
+
+impl fmt::Display for ConstantNegation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message())
+    }
+}
+
+impl std::error::Error for ConstantNegation {}
+
+impl DiagnosticMessage for ConstantNegation {
+    fn code(&self) -> usize {
+        661
+    }
+
+    fn message(&self) -> String {
+        "negation of a constant expression".to_owned()
+    }
+
+    fn labels(&self) -> Vec<Label> {
+        vec![
+            Label::primary("this negation always has the same result", self.not_span),
+            Label::context(
+                format!("this expression always resolves to {}", self.value),
+                self.expr_span,
+            ),
+        ]
+    }
+
+    fn notes(&self) -> Vec<Note> {
+        vec![Note::SeeErrorDocs]
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::value::Secrets;
+    use core::Value;
+    use vector_common::TimeZone;
+
+    use super::Not;
+    use crate::{
+        expression::Literal, state::Runtime, value, CompileConfig, Compiler, Expression, Span,
+        TargetValue, TypeState,
+    };
+
+    fn negate(source: &str, truthy_negation: bool) -> Result<Value, Vec<usize>> {
+        let ast = parser::parse(source).unwrap();
+        let mut config = CompileConfig::default();
+        config.set_truthy_negation(truthy_negation);
+
+        let program = Compiler::compile(&[], ast, &TypeState::default(), config)
+            .map_err(|diagnostics| diagnostics.iter().map(|d| d.code).collect::<Vec<_>>())?
+            .program;
+
+        let mut target = TargetValue {
+            value: value!({}),
+            metadata: value!({}),
+            secrets: Secrets::default(),
+        };
+        let mut state = Runtime::default();
+        let timezone = TimeZone::default();
+        let mut ctx = crate::Context::new(&mut target, &mut state, &timezone);
+
+        Ok(program.resolve(&mut ctx).unwrap())
+    }
+
+    #[test]
+    fn strict_negation_rejects_non_boolean() {
+        assert_eq!(negate(r#"!"foo""#, false), Err(vec![660]));
+        assert_eq!(negate("!true", false), Ok(value!(false)));
+    }
+
+    #[test]
+    fn reports_every_non_boolean_negation() {
+        assert_eq!(negate("!1 && !2", false), Err(vec![660, 660]));
+        assert_eq!(
+            negate(r#"x = !"foo" || true; !{ !null }"#, false),
+            Err(vec![660, 660])
+        );
+    }
+
+    #[test]
+    fn warns_about_constant_negation() {
+        fn warnings(source: &str, enabled: bool) -> Vec<(usize, Span)> {
+            let ast = parser::parse(source).unwrap();
+            let mut config = CompileConfig::default();
+            config.set_constant_negation_warning(enabled);
+
+            Compiler::compile(&[], ast, &TypeState::default(), config)
+                .unwrap_or_else(|_| panic!("expected compilation to succeed: {source}"))
+                .warnings
+                .iter()
+                .flat_map(|d| {
+                    d.labels
+                        .iter()
+                        .filter(|l| l.primary)
+                        .map(|l| (d.code, l.span))
+                })
+                .collect()
+        }
+
+        assert_eq!(warnings("!false", true), vec![(661, Span::new(0, 1))]);
+        assert_eq!(
+            warnings("x = 1\n!(true)", true),
+            vec![(661, Span::new(6, 7))]
+        );
+        assert_eq!(warnings("!false", false), vec![]);
+        assert!(!CompileConfig::default().constant_negation_warning());
+        assert_eq!(warnings("!(!true)", true), vec![(661, Span::new(2, 3))]);
+        assert_eq!(warnings("!(.x == 1)", true), vec![]);
+
+        // The negation is still folded and resolved.
+        assert_eq!(negate("!false", false), Ok(value!(true)));
+        assert_eq!(negate("!!false", false), Ok(value!(false)));
+    }
+
+    #[test]
+    fn rejects_deeply_nested_negation() {
+        fn compile(depth: usize, max_depth: Option<usize>) -> Result<(), Vec<usize>> {
+            let source = format!("{}true", "!".repeat(depth));
+            let ast = parser::parse(source).unwrap();
+            let mut config = CompileConfig::default();
+            config.set_constant_negation_warning(false);
+            if let Some(max_depth) = max_depth {
+                config.set_max_expression_depth(max_depth);
+            }
+
+            Compiler::compile(&[], ast, &TypeState::default(), config)
+                .map(|_| ())
+                .map_err(|diagnostics| diagnostics.iter().map(|d| d.code).collect())
+        }
+
+        // Each negation is one level, and the literal is another.
+        assert_eq!(compile(2, Some(3)), Ok(()));
+        assert_eq!(compile(3, Some(3)), Err(vec![112]));
+        assert_eq!(compile(100, Some(3)), Err(vec![112]));
+
+        assert_eq!(compile(255, None), Ok(()));
+        assert_eq!(compile(10_000, None), Err(vec![112]));
+    }
+
+    #[test]
+    fn compiles_long_else_if_chains() {
+        fn compile(source: String) -> Result<(), Vec<usize>> {
+            let ast = parser::parse(source).unwrap();
+            Compiler::compile(&[], ast, &TypeState::default(), CompileConfig::default())
+                .map(|_| ())
+                .map_err(|diagnostics| diagnostics.iter().map(|d| d.code).collect())
+        }
+
+        let branches = (0..300)
+            .map(|i| format!("if x == {i} {{ {i} }}"))
+            .collect::<Vec<_>>()
+            .join(" else ");
+        assert_eq!(compile(format!("x = 1\n{branches} else {{ -1 }}")), Ok(()));
+
+        // Expressions within the branches are still limited.
+        let nested = format!("{}true", "!".repeat(300));
+        assert_eq!(
+            compile(format!(
+                "x = 1\nif x == 0 {{ 0 }} else if x == 1 {{ {nested} }}"
+            )),
+            Err(vec![112])
+        );
+    }
+
+    #[test]
+    fn compiles_long_operation_chains() {
+        fn compile(source: String) -> Result<(), Vec<usize>> {
+            let ast = parser::parse(source).unwrap();
+            Compiler::compile(&[], ast, &TypeState::default(), CompileConfig::default())
+                .map(|_| ())
+                .map_err(|diagnostics| diagnostics.iter().map(|d| d.code).collect())
+        }
+
+        let terms = (0..1000).map(|i| format!("x == {i}")).collect::<Vec<_>>();
+        assert_eq!(compile(format!("x = 1\n{}", terms.join(" || "))), Ok(()));
+        assert_eq!(compile(vec!["1"; 1000].join(" + ")), Ok(()));
+
+        // Operands are still limited.
+        let nested = format!("{}true", "!".repeat(300));
+        assert_eq!(compile(format!("false || {nested}")), Err(vec![112]));
+    }
+
+    #[test]
+    fn from_expr_type_checks_inner_expression() {
+        let state = TypeState::default();
+
+        let not = Not::from_expr(Literal::Boolean(true).into(), &state).unwrap();
+        assert!(not.type_info(&state).result.is_boolean());
+        assert_eq!(not.to_string(), "!true");
+
+        let err = Not::from_expr(Literal::Integer(1).into(), &state).unwrap_err();
+        assert_eq!(diagnostic::DiagnosticMessage::code(&err), 660);
+    }
+
+    #[test]
+    fn fallible_negation_points_at_inner_expression() {
+        fn primary_spans(source: &str) -> Vec<(usize, Span)> {
+            let ast = parser::parse(source).unwrap();
+            let Err(diagnostics) =
+                Compiler::compile(&[], ast, &TypeState::default(), CompileConfig::default())
+            else {
+                panic!("expected compilation to fail: {source}");
+            };
+
+            diagnostics
+                .iter()
+                .flat_map(|d| {
+                    d.labels
+                        .iter()
+                        .filter(|l| l.primary)
+                        .map(|l| (d.code, l.span))
+                })
+                .collect()
+        }
+
+        // Non-boolean and fallible: the inner expression is the fix site, and compilation carries
+        // on to report the unhandled error as well.
+        assert_eq!(
+            primary_spans("!(1 / .x)"),
+            vec![(660, Span::new(1, 9)), (100, Span::new(2, 8))]
+        );
+
+        // Non-boolean and infallible: the negation is the fix site.
+        assert_eq!(primary_spans("!(1 / 2)"), vec![(660, Span::new(0, 1))]);
+
+        // Boolean but fallible: the unhandled error is reported on the inner expression.
+        assert_eq!(primary_spans("!(.x < 1)"), vec![(100, Span::new(2, 8))]);
+    }
+
+    #[test]
+    fn negated_range_check_is_rewritten() {
+        fn program(source: &str) -> String {
+            let ast = parser::parse(source).unwrap();
+            Compiler::compile(&[], ast, &TypeState::default(), CompileConfig::default())
+                .unwrap_or_else(|_| panic!("expected compilation to succeed: {source}"))
+                .program
+                .expressions
+                .to_string()
+        }
+
+        assert!(program("x = 3\n!(x >= 1 && x <= 5)").contains("x < 1 || x > 5"));
+        assert!(program("x = 3\n!(x > 1 && 5 > x)").contains("x <= 1 || 5 <= x"));
+
+        // Only conjunctions of ordering comparisons on side-effect free operands are rewritten.
+        assert!(program("x = 3\n!(x == 1 && x <= 5)").contains("!(x == 1 && x <= 5)"));
+        assert!(program("x = 3\n!(x >= 1 || x <= 5)").contains("!(x >= 1 || x <= 5)"));
+        assert!(program("x = 3\n!({ x } >= 1 && x <= 5)").contains("!("));
+
+        for x in ["0", "1", "3", "5", "6", "0.5", "1.0", "5.0", "5.5"] {
+            let source = format!("x = {x}\n!(x >= 1 && x <= 5)");
+            let x: f64 = x.parse().unwrap();
+            let want = !((1.0..=5.0).contains(&x));
+            assert_eq!(negate(&source, false), Ok(value!(want)), "{source}");
+        }
+    }
+
+    #[test]
+    fn negated_comparison_is_fused() {
+        fn program(source: &str) -> String {
+            let ast = parser::parse(source).unwrap();
+            Compiler::compile(&[], ast, &TypeState::default(), CompileConfig::default())
+                .unwrap_or_else(|_| panic!("expected compilation to succeed: {source}"))
+                .program
+                .expressions
+                .to_string()
+        }
+
+        assert_eq!(program("x = 3\n!(x > 1)"), program("x = 3\nx <= 1"));
+        assert_eq!(program("x = 3\n!(x >= 1)"), program("x = 3\nx < 1"));
+        assert_eq!(program("x = 3\n!(x < 1)"), program("x = 3\nx >= 1"));
+        assert_eq!(program("x = 3\n!(x <= 1)"), program("x = 3\nx > 1"));
+        assert!(program("x = 3\n!(x == 1)").contains("!(x == 1)"));
+
+        let operands = [
+            ("1", "1"),
+            ("1", "2"),
+            ("2", "1"),
+            ("1", "1.0"),
+            ("-1.5", "1"),
+            ("0.0", "-0.0"),
+            (r#""a""#, r#""a""#),
+            (r#""a""#, r#""b""#),
+            (r#"t'2021-01-01T00:00:00Z'"#, r#"t'2021-01-01T00:00:00Z'"#),
+        ];
+        for (lhs, rhs) in operands {
+            for (op, fused) in [(">", "<="), (">=", "<"), ("<", ">="), ("<=", ">")] {
+                let negated = negate(&format!("a = {lhs}\nb = {rhs}\n!(a {op} b)"), false);
+                let compared = negate(&format!("a = {lhs}\nb = {rhs}\na {fused} b"), false);
+                assert_eq!(negated, compared, "!({lhs} {op} {rhs})");
+            }
+        }
+
+        // Comparisons that can fail still have to be handled.
+        assert_eq!(negate("!(.a > 1)", false), Err(vec![100]));
+        assert_eq!(negate("!(.a > 1) ?? true", false), Ok(value!(true)));
+    }
+
+    #[test]
+    fn truthy_negation() {
+        let cases = [
+            ("!true", false),
+            ("!false", true),
+            (r#"!"foo""#, false),
+            (r#"!"""#, true),
+            ("!1", false),
+            ("!0", true),
+            ("!1.5", false),
+            ("!0.0", true),
+            ("!null", true),
+            ("![1]", false),
+            ("![]", true),
+            (r#"!{"foo": 1}"#, false),
+            ("!{}", true),
+            ("!r'foo'", false),
+            ("!t'2021-01-01T00:00:00Z'", false),
+        ];
+
+        for (source, want) in cases {
+            assert_eq!(negate(source, true), Ok(value!(want)), "{source}");
+        }
+    }
+}
@@ -78,58 +78,55 @@ impl Op {
             opcode,
         })
     }
-}
 
-impl Expression for Op {
-    fn resolve(&self, ctx: &mut Context) -> Resolved {
-        use ast::Opcode::{Add, And, Div, Eq, Err, Ge, Gt, Le, Lt, Merge, Mul, Ne, Or, Sub};
-        use value::Value::{Boolean, Null};
+    /// Negates a conjunction of two ordering comparisons, such as the range check
+    /// `.x >= 1 && .x <= 5`, by rewriting it into the disjunction of the inverted comparisons,
+    /// `.x < 1 || .x > 5`.
+    ///
+    /// Returns `None` if this isn't such a conjunction, or if any compared operand is something
+    /// other than a literal, a variable, or a path into the event or a variable.
+    pub(crate) fn negate_range_check(&self) -> Option<Self> {
+        use ast::Opcode::{And, Or};
 
-        match self.opcode {
-            Err => return self.lhs.resolve(ctx).or_else(|_| self.rhs.resolve(ctx)),
-            Or => {
-                return self
-                    .lhs
-                    .resolve(ctx)?
-                    .try_or(|| self.rhs.resolve(ctx))
-                    .map_err(Into::into);
-            }
-            And => {
-                return match self.lhs.resolve(ctx)? {
-                    Null | Boolean(false) => Ok(false.into()),
-                    v => v.try_and(self.rhs.resolve(ctx)?).map_err(Into::into),
-                };
+        if self.opcode != And {
+            return None;
+        }
+
+        let invert = |expr: &Expr| match expr {
+            Expr::Op(op) if is_pure_operand(&op.lhs) && is_pure_operand(&op.rhs) => {
+                op.negate_comparison()
             }
-            _ => (),
+            _ => None,
         };
 
-        let lhs = self.lhs.resolve(ctx)?;
-        let rhs = self.rhs.resolve(ctx)?;
+        Some(Op {
+            lhs: Box::new(invert(&self.lhs)?.into()),
+            rhs: Box::new(invert(&self.rhs)?.into()),
+            opcode: Or,
+        })
+    }
 
-        match self.opcode {
-            Mul => lhs.try_mul(rhs),
-            Div => lhs.try_div(rhs),
-            Add => lhs.try_add(rhs),
-            Sub => lhs.try_sub(rhs),
-            Eq => Ok(lhs.eq_lossy(&rhs).into()),
-            Ne => Ok((!lhs.eq_lossy(&rhs)).into()),
-            Gt => lhs.try_gt(rhs),
-            Ge => lhs.try_ge(rhs),
-            Lt => lhs.try_lt(rhs),
-            Le => lhs.try_le(rhs),
-            Merge => lhs.try_merge(rhs),
-            And | Or | Err => unreachable!(),
-        }
-        .map_err(Into::into)
+    /// Negates an ordering comparison, such as `.a > .b`, by rewriting it into the inverted
+    /// comparison, `.a <= .b`.
+    ///
+    /// VRL floats are never NaN, so values that can be compared are totally ordered, and the
+    /// inverted comparison is true exactly when the original one is false. Values that cannot be
+    /// compared, such as `null`, fail either comparison alike.
+    ///
+    /// Returns `None` if this isn't an ordering comparison.
+    pub(crate) fn negate_comparison(&self) -> Option<Self> {
+        Some(Op {
+            lhs: self.lhs.clone(),
+            rhs: self.rhs.clone(),
+            opcode: inverted_comparison(self.opcode)?,
+        })
     }
 
-    fn type_info(&self, state: &TypeState) -> TypeInfo {
+    /// The type of the operation, given the type of its left-hand side and the state after it.
+    fn type_info_with_lhs(&self, mut state: TypeState, mut lhs_def: TypeDef) -> TypeInfo {
         use ast::Opcode::{Add, And, Div, Eq, Err, Ge, Gt, Le, Lt, Merge, Mul, Ne, Or, Sub};
         use value::Kind as K;
 
-        let mut state = state.clone();
-        let mut lhs_def = self.lhs.apply_type_info(&mut state);
-
         // TODO: this is incorrect, but matches the existing behavior of the compiler
         // see: https://github.com/vectordotdev/vector/issues/13789
         // and: https://github.com/vectordotdev/vector/issues/13791
@@ -269,6 +266,100 @@ impl Expression for Op {
     }
 }
 
+/// Whether evaluating the expression only reads a value, without side effects.
+fn is_pure_operand(expr: &Expr) -> bool {
+    match expr {
+        Expr::Variable(_) => true,
+        #[cfg(feature = "expr-literal")]
+        Expr::Literal(_) => true,
+        #[cfg(feature = "expr-query")]
+        Expr::Query(query) => matches!(
+            query.target(),
+            expression::Target::Internal(_) | expression::Target::External(_)
+        ),
+        _ => false,
+    }
+}
+
+/// Returns the ordering comparison that is true exactly when the given one is false.
+fn inverted_comparison(opcode: ast::Opcode) -> Option<ast::Opcode> {
+    use ast::Opcode::{Ge, Gt, Le, Lt};
+
+    match opcode {
+        Ge => Some(Lt),
+        Gt => Some(Le),
+        Le => Some(Gt),
+        Lt => Some(Ge),
+        _ => None,
+    }
+}
+
+impl Expression for Op {
+    fn resolve(&self, ctx: &mut Context) -> Resolved {
+        use ast::Opcode::{Add, And, Div, Eq, Err, Ge, Gt, Le, Lt, Merge, Mul, Ne, Or, Sub};
+        use value::Value::{Boolean, Null};
+
+        match self.opcode {
+            Err => return self.lhs.resolve(ctx).or_else(|_| self.rhs.resolve(ctx)),
+            Or => {
+                return self
+                    .lhs
+                    .resolve(ctx)?
+                    .try_or(|| self.rhs.resolve(ctx))
+                    .map_err(Into::into);
+            }
+            And => {
+                return match self.lhs.resolve(ctx)? {
+                    Null | Boolean(false) => Ok(false.into()),
+                    v => v.try_and(self.rhs.resolve(ctx)?).map_err(Into::into),
+                };
+            }
+            _ => (),
+        };
+
+        let lhs = self.lhs.resolve(ctx)?;
+        let rhs = self.rhs.resolve(ctx)?;
+
+        match self.opcode {
+            Mul => lhs.try_mul(rhs),
+            Div => lhs.try_div(rhs),
+            Add => lhs.try_add(rhs),
+            Sub => lhs.try_sub(rhs),
+            Eq => Ok(lhs.eq_lossy(&rhs).into()),
+            Ne => Ok((!lhs.eq_lossy(&rhs)).into()),
+            Gt => lhs.try_gt(rhs),
+            Ge => lhs.try_ge(rhs),
+            Lt => lhs.try_lt(rhs),
+            Le => lhs.try_le(rhs),
+            Merge => lhs.try_merge(rhs),
+            And | Or | Err => unreachable!(),
+        }
+        .map_err(Into::into)
+    }
+
+    fn type_info(&self, state: &TypeState) -> TypeInfo {
+        // A chain of operations such as `a || b || c` nests in the left-hand side of each
+        // operation. Its type is computed in a loop rather than recursively, so that long chains
+        // don't overflow the stack.
+        let mut chain = vec![self];
+        let mut lhs = &*self.lhs;
+        while let Expr::Op(op) = lhs {
+            chain.push(op);
+            lhs = &*op.lhs;
+        }
+
+        let mut state = state.clone();
+        let mut lhs_def = lhs.apply_type_info(&mut state);
+        for op in chain.into_iter().rev() {
+            TypeInfo {
+                state,
+                result: lhs_def,
+            } = op.type_info_with_lhs(state, lhs_def);
+        }
+        TypeInfo::new(state, lhs_def)
+    }
+}
+
 impl fmt::Display for Op {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{} {} {}", self.lhs, self.opcode, self.rhs)
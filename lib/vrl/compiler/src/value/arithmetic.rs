@@ -283,22 +283,8 @@ impl VrlValueArithmetic for Value {
     }
 
     /// Similar to [`std::cmp::Eq`], but does a lossless comparison for integers
-    /// and floats.
+    /// and floats, including those nested in arrays and objects.
     fn eq_lossy(&self, rhs: &Self) -> bool {
-        use Value::{Float, Integer};
-
-        match self {
-            Integer(lhv) => rhs
-                .try_into_f64()
-                .map(|rhv| *lhv as f64 == rhv)
-                .unwrap_or(false),
-
-            Float(lhv) => rhs
-                .try_into_f64()
-                .map(|rhv| lhv.into_inner() == rhv)
-                .unwrap_or(false),
-
-            _ => self == rhs,
-        }
+        self.deep_eq(rhs)
     }
 }
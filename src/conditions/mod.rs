@@ -35,6 +35,9 @@ pub enum Condition {
     /// Matches an event with a [Datadog Search](https://docs.datadoghq.com/logs/explorer/search_syntax/) query.
     DatadogSearch(DatadogSearchRunner),
 
+    /// Matches an event if the inner condition does not match it.
+    Not(Box<Condition>),
+
     /// Matches any event.
     ///
     /// Used only for internal testing.
@@ -58,6 +61,10 @@ impl Condition {
             Condition::IsTrace => check_is_trace(e),
             Condition::Vrl(x) => x.check(e),
             Condition::DatadogSearch(x) => x.check(e),
+            Condition::Not(x) => {
+                let (result, event) = x.check(e);
+                (!result, event)
+            }
             Condition::AlwaysPass => (true, e),
             Condition::AlwaysFail => (false, e),
         }
@@ -74,6 +81,10 @@ impl Condition {
             Condition::IsTrace => check_is_trace_with_context(e),
             Condition::Vrl(x) => x.check_with_context(e),
             Condition::DatadogSearch(x) => x.check_with_context(e),
+            Condition::Not(x) => match x.check_with_context(e) {
+                (Ok(()), event) => (Err("negated condition matched".to_string()), event),
+                (Err(_), event) => (Ok(()), event),
+            },
             Condition::AlwaysPass => (Ok(()), e),
             Condition::AlwaysFail => (Ok(()), e),
         }
@@ -109,6 +120,12 @@ pub enum ConditionConfig {
 
     /// Matches an event with a [Datadog Search](https://docs.datadoghq.com/logs/explorer/search_syntax/) query.
     DatadogSearch(DatadogSearchConfig),
+
+    /// Matches an event if the inner condition does not match it.
+    Not {
+        /// The condition to negate.
+        condition: Box<AnyCondition>,
+    },
 }
 
 impl ConditionConfig {
@@ -119,6 +136,9 @@ impl ConditionConfig {
             ConditionConfig::IsTrace => Ok(Condition::IsTrace),
             ConditionConfig::Vrl(x) => x.build(enrichment_tables),
             ConditionConfig::DatadogSearch(x) => x.build(enrichment_tables),
+            ConditionConfig::Not { condition } => Ok(Condition::Not(Box::new(
+                condition.build(enrichment_tables)?,
+            ))),
         }
     }
 }
@@ -206,6 +226,10 @@ mod tests {
     use serde::Deserialize;
 
     use super::*;
+    use crate::event::{
+        metric::{Metric, MetricKind, MetricValue},
+        LogEvent,
+    };
 
     #[derive(Deserialize, Debug)]
     struct Test {
@@ -234,4 +258,61 @@ mod tests {
             format!("{:?}", conf.condition)
         )
     }
+
+    #[test]
+    fn deserialize_anycondition_not() {
+        let conf: Test = toml::from_str(indoc! {r#"
+            condition.type = "not"
+            condition.condition.type = "is_log"
+        "#})
+        .unwrap();
+
+        assert_eq!(
+            r#"Map(Not { condition: Map(IsLog) })"#,
+            format!("{:?}", conf.condition)
+        )
+    }
+
+    #[test]
+    fn not_inverts_inner_condition() {
+        let log = Event::from(LogEvent::from("i am a log"));
+        let metric = Event::from(Metric::new(
+            "test metric",
+            MetricKind::Incremental,
+            MetricValue::Counter { value: 1.0 },
+        ));
+        let not = |condition: AnyCondition| -> AnyCondition {
+            ConditionConfig::Not {
+                condition: Box::new(condition),
+            }
+            .into()
+        };
+
+        let cases = [
+            (not(ConditionConfig::IsLog.into()), &log, false),
+            (not(ConditionConfig::IsLog.into()), &metric, true),
+            (not(ConditionConfig::IsMetric.into()), &log, true),
+            (
+                not(AnyCondition::String(r#".message == "i am a log""#.into())),
+                &log,
+                false,
+            ),
+            (
+                not(AnyCondition::String("exists(.missing)".into())),
+                &log,
+                true,
+            ),
+            (not(not(ConditionConfig::IsLog.into())), &log, true),
+        ];
+
+        for (config, event, want) in cases {
+            let condition = config.build(&Default::default()).unwrap();
+            assert_eq!(condition.check(event.clone()).0, want, "{condition:?}");
+            assert_eq!(
+                condition.check_with_context(event.clone()).0.is_ok(),
+                want,
+                "{condition:?}"
+            );
+        }
+    }
 }
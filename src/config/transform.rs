@@ -91,6 +91,13 @@ pub struct TransformContext {
     /// compiler such that type coercion becomes less of a need for operators writing VRL programs.
     pub merged_schema_definition: schema::Definition,
 
+    /// The outputs of the transform that are the input of another component, identified like in
+    /// `schema_definitions`.
+    ///
+    /// This is determined when the transform is built, so consumers added or removed by a reload
+    /// that does not rebuild the transform are not accounted for.
+    pub consumed_outputs: HashSet<Option<String>>,
+
     pub schema: SchemaOptions,
 }
 
@@ -102,6 +109,7 @@ impl Default for TransformContext {
             enrichment_tables: Default::default(),
             schema_definitions: HashMap::from([(None, schema::Definition::any())]),
             merged_schema_definition: schema::Definition::any(),
+            consumed_outputs: HashSet::new(),
             schema: SchemaOptions::default(),
         }
     }
@@ -179,6 +187,15 @@ pub trait TransformConfig: NamedComponent + core::fmt::Debug + Send + Sync {
         Ok(())
     }
 
+    /// Gets the warnings about the configuration of the transform.
+    ///
+    /// These point out configurations that are valid, but are probably a mistake, such as options
+    /// that have no effect. They are reported when the configuration is loaded, and by
+    /// `vector validate`.
+    fn warnings(&self) -> Vec<String> {
+        Vec::new()
+    }
+
     /// Whether or not concurrency should be enabled for this transform.
     ///
     /// When enabled, this transform may be run in parallel in order to attempt to maximize
@@ -377,6 +377,16 @@ pub fn warnings(config: &Config) -> Vec<String> {
         }
     }
 
+    for (key, transform) in config.transforms.iter() {
+        warnings.extend(
+            transform
+                .inner
+                .warnings()
+                .into_iter()
+                .map(|warning| format!("Transform \"{key}\" {warning}")),
+        );
+    }
+
     warnings
 }
 
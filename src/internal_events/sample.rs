@@ -1,16 +1,67 @@
+use std::time::Duration;
+
 use crate::emit;
-use metrics::counter;
+use metrics::{counter, gauge};
 use vector_core::internal_event::{ComponentEventsDropped, InternalEvent, INTENTIONAL};
 
 #[derive(Debug)]
-pub struct SampleEventDiscarded;
+pub struct SampleEventDiscarded {
+    pub count: usize,
+}
 
 impl InternalEvent for SampleEventDiscarded {
     fn emit(self) {
-        counter!("events_discarded_total", 1); // Deprecated.
+        counter!("events_discarded_total", self.count as u64); // Deprecated.
         emit!(ComponentEventsDropped::<INTENTIONAL> {
-            count: 1,
+            count: self.count,
             reason: "Sample discarded."
         })
     }
 }
+
+#[derive(Debug)]
+pub struct SampleEventExcluded;
+
+impl InternalEvent for SampleEventExcluded {
+    fn emit(self) {
+        counter!("sample_excluded_events_total", 1);
+    }
+}
+
+#[derive(Debug)]
+pub struct SampleEventPassed;
+
+impl InternalEvent for SampleEventPassed {
+    fn emit(self) {
+        counter!("sample_passed_events_total", 1);
+    }
+}
+
+#[derive(Debug)]
+pub struct SampleNoOutput {
+    pub discarded: u64,
+    pub window: Duration,
+}
+
+impl InternalEvent for SampleNoOutput {
+    fn emit(self) {
+        warn!(
+            message = "Sample discarded all sampled events in window.",
+            discarded = self.discarded,
+            window_secs = self.window.as_secs(),
+            internal_log_rate_limit = true,
+        );
+        counter!("sample_no_output_windows_total", 1);
+    }
+}
+
+#[derive(Debug)]
+pub struct SamplePassRatio {
+    pub ratio: f64,
+}
+
+impl InternalEvent for SamplePassRatio {
+    fn emit(self) {
+        gauge!("sample_pass_ratio", self.ratio);
+    }
+}
@@ -5,7 +5,7 @@ use codecs::{
 use indexmap::map::IndexMap;
 use serde::{Deserialize, Serialize};
 use vector_config::configurable_component;
-pub use vector_core::serde::{bool_or_struct, skip_serializing_if_default};
+pub use vector_core::serde::{bool_or_struct, skip_serializing_if_default, HumanDuration};
 
 pub const fn default_true() -> bool {
     true
@@ -367,12 +367,22 @@ pub async fn build_pieces(
             schema_definitions.insert(output.port, definition);
         }
 
+        let consumed_outputs = config
+            .transforms()
+            .map(|(_, other)| &other.inputs[..])
+            .chain(config.sinks().map(|(_, sink)| &sink.inputs[..]))
+            .flatten()
+            .filter(|input| &input.component == key)
+            .map(|input| input.port.clone())
+            .collect();
+
         let context = TransformContext {
             key: Some(key.clone()),
             globals: config.global.clone(),
             enrichment_tables: enrichment_tables.clone(),
             schema_definitions,
             merged_schema_definition: merged_definition.clone(),
+            consumed_outputs,
             schema: config.schema,
         };
 
@@ -87,13 +87,14 @@ mod test {
     use tokio::sync::mpsc;
     use tokio_stream::wrappers::ReceiverStream;
     use vector_core::event::{Metric, MetricKind, MetricValue};
+    use vrl::VrlRuntime;
 
     use super::*;
     use crate::{
-        conditions::ConditionConfig,
+        conditions::{ConditionConfig, VrlConfig},
         event::{Event, LogEvent},
         test_util::components::assert_transform_compliance,
-        transforms::test::create_topology,
+        transforms::test::{create_topology, transform_one},
     };
 
     #[test]
@@ -128,4 +129,23 @@ mod test {
         })
         .await;
     }
+
+    #[test]
+    fn filter_negated_vrl_condition() {
+        // Every VRL runtime must agree on negated conditions.
+        for runtime in [VrlRuntime::Ast] {
+            let condition = AnyCondition::from(ConditionConfig::Vrl(VrlConfig {
+                source: "!exists(.drop_me)".to_owned(),
+                runtime,
+            }));
+            let mut filter = Filter::new(condition.build(&Default::default()).unwrap());
+
+            let kept = Event::from(LogEvent::from("keep me"));
+            assert_eq!(transform_one(&mut filter, kept.clone()), Some(kept));
+
+            let mut dropped = LogEvent::from("drop me");
+            dropped.insert("drop_me", true);
+            assert_eq!(transform_one(&mut filter, dropped.into()), None);
+        }
+    }
 }
@@ -29,6 +29,8 @@ pub mod sample;
 pub mod tag_cardinality_limit;
 #[cfg(feature = "transforms-throttle")]
 pub mod throttle;
+#[cfg(feature = "transforms-sample")]
+pub(crate) mod windowed_counter;
 
 use vector_config::{configurable_component, NamedComponent};
 pub use vector_core::transform::{
@@ -1,16 +1,51 @@
+use std::{
+    hash::Hasher,
+    mem,
+    num::NonZeroUsize,
+    time::{Duration, Instant},
+};
+
+use lookup::{lookup_v2::parse_value_path, path};
+use lru::LruCache;
+use serde_with::serde_as;
+use sha2::{Digest, Sha256};
+use snafu::Snafu;
+use twox_hash::XxHash64;
 use vector_config::configurable_component;
-use vector_core::config::LogNamespace;
+use vector_core::{config::LogNamespace, EstimatedJsonEncodedSizeOf};
 
 use crate::{
-    conditions::{AnyCondition, Condition},
-    config::{DataType, GenerateConfig, Input, Output, TransformConfig, TransformContext},
-    event::Event,
-    internal_events::SampleEventDiscarded,
+    conditions::{AnyCondition, Condition, ConditionalConfig, VrlConfig},
+    config::{
+        DataType, GenerateConfig, GlobalOptions, Input, Output, TransformConfig, TransformContext,
+    },
+    event::{Event, Value},
+    internal_events::{
+        SampleEventDiscarded, SampleEventExcluded, SampleEventPassed, SampleNoOutput,
+        SamplePassRatio,
+    },
     schema,
-    transforms::{FunctionTransform, OutputBuffer, Transform},
+    transforms::{
+        windowed_counter::WindowedCounter, FunctionTransform, OutputBuffer, SyncTransform,
+        Transform, TransformOutputsBuf,
+    },
 };
 
+const SAMPLED_OUTPUT: &str = "sampled";
+const DROPPED_OUTPUT: &str = "dropped";
+
+/// The number of discarded events to report at once, unless an event is passed first.
+const DISCARDED_REPORT_COUNT: usize = 1000;
+
+/// The longest time for which discarded events are held before being reported, unless that many
+/// are discarded first.
+const DISCARDED_REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The interval over which throughput is measured to adapt the rate to `target_rate`.
+const ADAPTIVE_WINDOW: Duration = Duration::from_secs(1);
+
 /// Configuration for the `sample` transform.
+#[serde_as]
 #[configurable_component(transform("sample"))]
 #[derive(Clone, Debug)]
 #[serde(deny_unknown_fields)]
@@ -18,9 +53,37 @@ pub struct SampleConfig {
     /// The rate at which events will be forwarded, expressed as `1/N`.
     ///
     /// For example, `rate = 10` means 1 out of every 10 events will be forwarded and the rest will
-    /// be dropped.
+    /// be dropped. It must be greater than 0, unless `min_rate` is set.
     pub rate: u64,
 
+    /// The name of the log field holding the rate at which to sample each event, overriding `rate`.
+    ///
+    /// Events whose field is missing or does not hold a positive integer are sampled at `rate`. The
+    /// rate read from an event is still kept within `min_rate` and `max_rate`, and is the one
+    /// annotated in `sample_rate`.
+    #[configurable(metadata(docs::examples = "tenant.sample_rate",))]
+    pub rate_field: Option<String>,
+
+    /// The lowest rate at which events will be forwarded.
+    ///
+    /// The `rate` is raised to this value, and `sample_rate` is annotated with the raised rate,
+    /// if it is lower. It must be greater than 0.
+    pub min_rate: Option<u64>,
+
+    /// The highest rate at which events will be forwarded.
+    ///
+    /// The `rate` is lowered to this value, and `sample_rate` is annotated with the lowered rate,
+    /// if it is higher. It must be greater than 0.
+    pub max_rate: Option<u64>,
+
+    /// The number of sampled events to forward per second, adapting the rate to the throughput.
+    ///
+    /// When set, `rate` is only the initial rate. At the end of each second, the rate is adjusted
+    /// so that the throughput of that second would have been sampled down to `target_rate`,
+    /// within the `min_rate` and `max_rate` bounds. Events passed by the `exclude` condition are
+    /// not counted.
+    pub target_rate: Option<u64>,
+
     /// The name of the log field whose value will be hashed to determine if the event should be
     /// passed.
     ///
@@ -30,16 +93,267 @@ pub struct SampleConfig {
     #[configurable(metadata(docs::examples = "message",))]
     pub key_field: Option<String>,
 
+    /// The names of log fields to hash, in order, when `key_field` is unset or missing from the
+    /// event.
+    ///
+    /// The first of these fields that is present on the event is used. Events that have none of
+    /// them are count rated, or hashed whole when `deterministic` is enabled.
+    #[serde(default)]
+    #[configurable(metadata(
+        docs::examples = "trace_id",
+        docs::examples = "request_id",
+        docs::examples = "session_id",
+    ))]
+    pub key_field_fallback: Vec<String>,
+
+    /// The names of log fields whose values are hashed together to determine if an event should be
+    /// passed, when no key field is present on the event.
+    ///
+    /// Events with the same values in these fields share a sampling decision, regardless of their
+    /// other fields. Missing fields are hashed as `null`. This takes precedence over
+    /// `deterministic`.
+    #[serde(default)]
+    #[configurable(metadata(docs::examples = "host", docs::examples = "message",))]
+    pub hash_fields: Vec<String>,
+
+    /// The hash function used to determine if an event should be passed, when sampling by
+    /// `key_field` or with `deterministic` enabled.
+    ///
+    /// Changing the hash function changes which events are passed. Use a portable hash function
+    /// to pass the same events as another system sampling by the same key at the same rate.
+    #[serde(default)]
+    pub hash_function: SampleHashFunction,
+
+    /// A seed mixed into the hash used to determine if an event should be passed, to pass a
+    /// different set of events at the same rate.
+    ///
+    /// If left unspecified, the global `sample_seed` option is used, if set. Events that are count
+    /// rated are not affected.
+    pub seed: Option<u64>,
+
     /// A logical condition used to exclude events from sampling.
     pub exclude: Option<AnyCondition>,
+
+    /// The levels of events that are always passed, without being sampled.
+    ///
+    /// An event is passed if the value of its `level_field` is exactly one of these levels, in
+    /// addition to the events passed by the `exclude` condition.
+    #[serde(default)]
+    #[configurable(metadata(docs::examples = "error", docs::examples = "warn",))]
+    pub always_keep_levels: Vec<String>,
+
+    /// The name of the log field holding the level of an event, used by `always_keep_levels`.
+    #[serde(default = "default_level_field")]
+    pub level_field: String,
+
+    /// The size, in bytes, below which events are always passed, without being sampled.
+    ///
+    /// This keeps small events while sampling the large ones, which make up most of the volume
+    /// sent to sinks. The size of an event is measured by `size_estimator`. Events passed for
+    /// their size are treated like those passed by the `exclude` condition.
+    pub size_threshold_bytes: Option<usize>,
+
+    /// How the size of an event is measured for `size_threshold_bytes`.
+    #[serde(default)]
+    pub size_estimator: SampleSizeEstimator,
+
+    /// The name of the log field in which to store the number of events discarded since the
+    /// previous event was passed.
+    ///
+    /// If left unspecified, the number of discarded events is not recorded.
+    #[configurable(metadata(docs::examples = "sample_gap",))]
+    pub gap_field: Option<String>,
+
+    /// The name of the field in which to store the hash bucket that an event fell in.
+    ///
+    /// The bucket is the hash of the event's key modulo the rate, so events passed by sampling are
+    /// always in bucket `0`. Events sent to the `dropped` output are annotated with their bucket as
+    /// well, letting downstream consumers reproduce the sampling decision. Count rated events are
+    /// annotated with their position within each `rate` events. Events passed by the `exclude`
+    /// condition are not annotated.
+    ///
+    /// If left unspecified, the bucket is not recorded.
+    #[configurable(metadata(docs::examples = "sample_bucket",))]
+    pub bucket_field: Option<String>,
+
+    /// Whether to hash the whole event to determine if it should be passed, when `key_field` is
+    /// unset or missing from the event.
+    ///
+    /// This makes sampling independent of the order in which events arrive, at the cost of
+    /// serializing each of those events. When disabled, those events are count rated.
+    #[serde(default = "crate::serde::default_false")]
+    pub deterministic: bool,
+
+    /// The interval over which to measure the ratio of sampled events that were passed.
+    ///
+    /// When set, the observed ratio is emitted as the `sample_pass_ratio` gauge at the end of each
+    /// interval. It can differ from `1/rate` when the values of `key_field` are not uniformly
+    /// distributed. Events passed by the `exclude` condition are not counted.
+    #[serde_as(as = "crate::serde::HumanDuration")]
+    #[serde(default)]
+    #[configurable(metadata(docs::examples = "1m",))]
+    pub pass_ratio_window: Option<Duration>,
+
+    /// The interval after which to warn that no sampled event was passed.
+    ///
+    /// When set, a warning is logged and the `sample_no_output_windows_total` counter is incremented
+    /// at the end of each interval in which all sampled events were discarded. Events passed by
+    /// the `exclude` condition are not counted.
+    #[serde_as(as = "crate::serde::HumanDuration")]
+    #[serde(default)]
+    #[configurable(metadata(docs::examples = "5m",))]
+    pub no_output_window: Option<Duration>,
+
+    /// Whether to multiply the rate by the `sample_rate` already present on an event, rather than
+    /// overwriting it.
+    ///
+    /// This records the cumulative rate of events that pass through several `sample` transforms in
+    /// series. Events without a numeric `sample_rate` are annotated with `rate` as usual.
+    #[serde(default = "crate::serde::default_false")]
+    pub multiply_existing: bool,
+
+    /// Whether to annotate sampled events in their metadata rather than in the `sample_rate` field.
+    ///
+    /// When set, the rate is written to `%sample.rate` and `%sample.sampled` is set to `true`,
+    /// leaving the event itself unmodified. `multiply_existing` then reads the rate from
+    /// `%sample.rate` as well. The `gap_field` is still written to the event.
+    #[serde(default = "crate::serde::default_false")]
+    pub annotate_metadata: bool,
+
+    /// Whether to pass all events to the default output, and send the sampled events to the
+    /// `sampled` output instead of discarding the rest.
+    ///
+    /// This allows comparing the sampled events against the full stream before switching a
+    /// pipeline to sampling.
+    #[serde(default = "crate::serde::default_false")]
+    pub shadow_output: bool,
+
+    /// Whether to send the events that are not sampled to the `dropped` output, rather than
+    /// discarding them.
+    ///
+    /// The sampled events are sent to the default output as usual. If nothing consumes the
+    /// `dropped` output when the transform is built, its events are discarded, and counted as
+    /// such. This cannot be combined with `shadow_output`.
+    #[serde(default = "crate::serde::default_false")]
+    pub dropped_output: bool,
+
+    /// The interval after the transform is built during which all events are passed without being
+    /// sampled.
+    ///
+    /// A transform rebuilt by a configuration reload starts counting afresh, which skews the
+    /// sampling of the events in flight at the time of the reload. Passing them as-is for a short
+    /// while avoids that discontinuity. Events passed during this interval are not annotated.
+    #[serde_as(as = "crate::serde::HumanDuration")]
+    #[serde(default)]
+    #[configurable(metadata(docs::examples = "5s",))]
+    pub passthrough_on_reload_gap: Option<Duration>,
+
+    /// The number of recently seen `key_field` values whose hash bucket is cached.
+    ///
+    /// When set, events whose key value was recently sampled at the same rate reuse its bucket
+    /// instead of hashing the value again. This speeds up streams whose keys repeat in bursts,
+    /// without changing which events are passed.
+    ///
+    /// Only the `sha256` hash function uses the cache, as looking up a cached bucket takes longer
+    /// than hashing the value with the others.
+    pub decision_cache_size: Option<NonZeroUsize>,
+}
+
+fn default_level_field() -> String {
+    "level".to_string()
+}
+
+/// The hash function used by the `sample` transform.
+#[configurable_component]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SampleHashFunction {
+    /// [SeaHash][seahash], which is fast but not implemented by most other systems.
+    ///
+    /// [seahash]: https://docs.rs/seahash
+    #[default]
+    Seahash,
+
+    /// SHA-256, with the digest interpreted as a big-endian integer.
+    Sha256,
+
+    /// 64-bit [xxHash][xxhash] (XXH64), with a seed of 0 unless `seed` is set.
+    ///
+    /// [xxhash]: https://cyan4973.github.io/xxHash/
+    Xxhash,
+}
+
+impl SampleHashFunction {
+    /// Whether hashing is slow enough for the decision cache to pay off.
+    const fn uses_decision_cache(self) -> bool {
+        matches!(self, Self::Sha256)
+    }
+
+    /// Hashes `bytes`, mixed with `seed` if set, into one of `rate` buckets. Events in bucket 0 are
+    /// passed.
+    fn bucket(self, bytes: &[u8], rate: u64, seed: Option<u64>) -> u64 {
+        match self {
+            Self::Seahash => {
+                let hash = match seed {
+                    Some(seed) => seahash::hash_seeded(bytes, seed, 0, 0, 0),
+                    None => seahash::hash(bytes),
+                };
+                hash % rate
+            }
+            Self::Sha256 => {
+                let mut hasher = Sha256::new();
+                if let Some(seed) = seed {
+                    hasher.update(seed.to_be_bytes());
+                }
+                hasher.update(bytes);
+
+                // Reduce the whole digest one byte at a time, so the result is the same as the
+                // remainder of the 256-bit integer.
+                let rate = u128::from(rate);
+                let bucket = hasher
+                    .finalize()
+                    .iter()
+                    .fold(0, |bucket, byte| ((bucket << 8) | u128::from(*byte)) % rate);
+                bucket as u64
+            }
+            Self::Xxhash => {
+                let mut hasher = XxHash64::with_seed(seed.unwrap_or(0));
+                hasher.write(bytes);
+                hasher.finish() % rate
+            }
+        }
+    }
 }
 
 impl GenerateConfig for SampleConfig {
     fn generate_config() -> toml::Value {
         toml::Value::try_from(Self {
             rate: 10,
+            rate_field: None,
+            min_rate: None,
+            max_rate: None,
+            target_rate: None,
             key_field: None,
+            key_field_fallback: Vec::new(),
+            hash_fields: Vec::new(),
+            hash_function: SampleHashFunction::default(),
+            seed: None,
             exclude: None::<AnyCondition>,
+            always_keep_levels: Vec::new(),
+            level_field: default_level_field(),
+            size_threshold_bytes: None,
+            size_estimator: SampleSizeEstimator::default(),
+            gap_field: None,
+            bucket_field: None,
+            deterministic: false,
+            pass_ratio_window: None,
+            no_output_window: None,
+            multiply_existing: false,
+            annotate_metadata: false,
+            shadow_output: false,
+            dropped_output: false,
+            passthrough_on_reload_gap: None,
+            decision_cache_size: None,
         })
         .unwrap()
     }
@@ -48,14 +362,79 @@ impl GenerateConfig for SampleConfig {
 #[async_trait::async_trait]
 impl TransformConfig for SampleConfig {
     async fn build(&self, context: &TransformContext) -> crate::Result<Transform> {
-        Ok(Transform::function(Sample::new(
+        if let (Some(min_rate), Some(max_rate)) = (self.min_rate, self.max_rate) {
+            if min_rate > max_rate {
+                return Err(Box::new(ConfigError::RateBounds { min_rate, max_rate }));
+            }
+        }
+
+        if self.min_rate == Some(0) {
+            return Err(Box::new(ConfigError::ZeroMinRate));
+        }
+
+        if self.max_rate == Some(0) {
+            return Err(Box::new(ConfigError::ZeroMaxRate));
+        }
+
+        if self.target_rate == Some(0) {
+            return Err(Box::new(ConfigError::ZeroTargetRate));
+        }
+
+        if self.shadow_output && self.dropped_output {
+            return Err(Box::new(ConfigError::ShadowAndDroppedOutputs));
+        }
+
+        if self.rate == 0 && self.min_rate.is_none() {
+            return Err(Box::new(ConfigError::ZeroRate));
+        }
+
+        if let Some(key_field) = self.key_field_missing_from(&context.merged_schema_definition) {
+            warn!(
+                message = "The `key_field` is not present in the schema of any input, so events without it will not be sampled by key.",
+                key_field = %key_field,
+            );
+        }
+
+        let sample = Sample::new(
             self.rate,
             self.key_field.clone(),
             self.exclude
                 .as_ref()
                 .map(|condition| condition.build(&context.enrichment_tables))
                 .transpose()?,
-        )))
+        )
+        .with_always_keep(self.always_keep(&context.enrichment_tables)?)
+        .with_size_threshold(self.size_threshold_bytes, self.size_estimator)
+        .with_rate_field(self.rate_field.clone())
+        .with_key_field_fallback(self.key_field_fallback.clone())
+        .with_hash_fields(self.hash_fields.clone())
+        .with_rate_bounds(self.min_rate, self.max_rate)
+        .with_target_rate(self.target_rate)
+        .with_hash_function(self.hash_function)
+        .with_seed(self.seed(&context.globals))
+        .with_gap_field(self.gap_field.clone())
+        .with_bucket_field(self.bucket_field.clone())
+        .with_deterministic(self.deterministic)
+        .with_pass_ratio_window(self.pass_ratio_window)
+        .with_no_output_window(self.no_output_window)
+        .with_multiply_existing(self.multiply_existing)
+        .with_annotate_metadata(self.annotate_metadata)
+        .with_passthrough_grace(self.passthrough_on_reload_gap)
+        .with_decision_cache_size(
+            self.decision_cache_size
+                .filter(|_| self.hash_function.uses_decision_cache()),
+        );
+
+        Ok(if self.shadow_output {
+            Transform::synchronous(ShadowSample::new(sample))
+        } else if self.dropped_output {
+            let consumed = context
+                .consumed_outputs
+                .contains(&Some(DROPPED_OUTPUT.to_owned()));
+            Transform::synchronous(DroppedOutputSample::new(sample, consumed))
+        } else {
+            Transform::function(sample)
+        })
     }
 
     fn input(&self) -> Input {
@@ -63,73 +442,856 @@ impl TransformConfig for SampleConfig {
     }
 
     fn outputs(&self, merged_definition: &schema::Definition, _: LogNamespace) -> Vec<Output> {
-        vec![Output::default(DataType::Log | DataType::Trace)
-            .with_schema_definition(merged_definition.clone())]
+        let default_output = Output::default(DataType::Log | DataType::Trace)
+            .with_schema_definition(merged_definition.clone());
+
+        if self.shadow_output {
+            vec![
+                default_output.clone(),
+                default_output.with_port(SAMPLED_OUTPUT),
+            ]
+        } else if self.dropped_output {
+            vec![
+                default_output.clone(),
+                default_output.with_port(DROPPED_OUTPUT),
+            ]
+        } else {
+            vec![default_output]
+        }
+    }
+
+    fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if self.fixed_rate() == Some(1) {
+            warnings.push("passes all events, as its sample rate is always 1".to_owned());
+        }
+        if self.decision_cache_size.is_some() && !self.hash_function.uses_decision_cache() {
+            warnings.push(
+                "ignores `decision_cache_size`, as only the `sha256` hash function uses it"
+                    .to_owned(),
+            );
+        }
+        warnings
+    }
+}
+
+#[derive(Debug, Snafu)]
+pub enum ConfigError {
+    #[snafu(display("`rate` must be greater than 0, unless `min_rate` is set"))]
+    ZeroRate,
+
+    #[snafu(display("`min_rate` ({min_rate}) must not be greater than `max_rate` ({max_rate})"))]
+    RateBounds { min_rate: u64, max_rate: u64 },
+
+    #[snafu(display("`min_rate` must be greater than 0"))]
+    ZeroMinRate,
+
+    #[snafu(display("`max_rate` must be greater than 0"))]
+    ZeroMaxRate,
+
+    #[snafu(display("`target_rate` must be greater than 0"))]
+    ZeroTargetRate,
+
+    #[snafu(display("`shadow_output` and `dropped_output` cannot both be enabled"))]
+    ShadowAndDroppedOutputs,
+
+    #[snafu(display("`level_field` ({level_field:?}) is not a valid field path"))]
+    InvalidLevelField { level_field: String },
+}
+
+impl SampleConfig {
+    /// Builds the condition passing events whose level is one of `always_keep_levels`, if any.
+    fn always_keep(
+        &self,
+        enrichment_tables: &enrichment::TableRegistry,
+    ) -> crate::Result<Option<Condition>> {
+        if self.always_keep_levels.is_empty() {
+            return Ok(None);
+        }
+
+        let level_field = parse_value_path(&self.level_field).map_err(|_| {
+            Box::new(ConfigError::InvalidLevelField {
+                level_field: self.level_field.clone(),
+            })
+        })?;
+        let levels =
+            serde_json::to_string(&self.always_keep_levels).expect("strings are serializable");
+        VrlConfig {
+            source: format!("includes({levels}, .{level_field})"),
+            runtime: Default::default(),
+        }
+        .build(enrichment_tables)
+        .map(Some)
+    }
+
+    /// Returns the rate at which every event is sampled, if it does not depend on the event or on
+    /// the throughput.
+    fn fixed_rate(&self) -> Option<u64> {
+        if self.max_rate == Some(1) {
+            return Some(1);
+        }
+        if self.rate_field.is_some() || self.target_rate.is_some() {
+            return None;
+        }
+        let rate = self
+            .min_rate
+            .map_or(self.rate, |min_rate| self.rate.max(min_rate));
+        Some(self.max_rate.map_or(rate, |max_rate| rate.min(max_rate)))
     }
+
+    /// Returns the `seed`, or else the global `sample_seed`.
+    const fn seed(&self, globals: &GlobalOptions) -> Option<u64> {
+        match self.seed {
+            Some(seed) => Some(seed),
+            None => globals.sample_seed,
+        }
+    }
+
+    /// Returns the `key_field` if the schema guarantees that it is never present on an event.
+    fn key_field_missing_from(&self, definition: &schema::Definition) -> Option<&str> {
+        let key_field = self.key_field.as_deref()?;
+        let path = parse_value_path(key_field).ok()?;
+        definition
+            .event_kind()
+            .at_path(&path)
+            .is_undefined()
+            .then_some(key_field)
+    }
+}
+
+/// The outcome of [`Sample::decide`] for an event.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SampleDecision {
+    /// The event is passed.
+    Pass { via: PassVia },
+
+    /// The event is discarded.
+    Drop,
+}
+
+/// How the `sample` transform measures the size of an event.
+#[configurable_component]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SampleSizeEstimator {
+    /// An estimate of the size of the event encoded as JSON, which is cheap to compute.
+    #[default]
+    EstimatedJson,
+
+    /// The exact size of the event encoded as JSON, at the cost of serializing each event.
+    Json,
+}
+
+impl SampleSizeEstimator {
+    fn size_of(self, event: &Event) -> usize {
+        match self {
+            Self::EstimatedJson => event.estimated_json_encoded_size_of(),
+            Self::Json => {
+                let value = match event {
+                    Event::Log(event) => event.value(),
+                    Event::Trace(event) => event.value(),
+                    Event::Metric(_) => panic!("component can never receive metric events"),
+                };
+                serde_json::to_vec(value)
+                    .expect("value is always serializable")
+                    .len()
+            }
+        }
+    }
+}
+
+/// The reason an event is passed by the `sample` transform.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PassVia {
+    /// The event matched the `exclude` condition, has one of the `always_keep_levels`, or is
+    /// smaller than the `size_threshold_bytes`, and so was not sampled.
+    Condition,
+
+    /// The event was selected by sampling.
+    Sampling,
+
+    /// The event arrived within the grace period after the transform was built, and so was not
+    /// sampled.
+    Passthrough,
 }
 
 #[derive(Clone)]
 pub struct Sample {
     rate: u64,
+    rate_field: Option<String>,
+    min_rate: Option<u64>,
+    max_rate: Option<u64>,
+    adaptive: Option<AdaptiveRate>,
     key_field: Option<String>,
+    key_field_fallback: Vec<String>,
+    hash_fields: Vec<String>,
     exclude: Option<Condition>,
+    always_keep: Option<Condition>,
+    size_threshold: Option<usize>,
+    size_estimator: SampleSizeEstimator,
+    hash_function: SampleHashFunction,
+    seed: Option<u64>,
+    decision_cache: Option<DecisionCache>,
+    gap_field: Option<String>,
+    bucket_field: Option<String>,
+    deterministic: bool,
+    pass_ratio: Option<WindowedCounter<bool>>,
+    no_output: Option<NoOutputWindow>,
+    multiply_existing: bool,
+    annotate_metadata: bool,
+    passthrough_until: Option<Instant>,
     count: u64,
+    gap: u64,
+    discarded: PendingDiscards,
 }
 
 impl Sample {
     pub const fn new(rate: u64, key_field: Option<String>, exclude: Option<Condition>) -> Self {
         Self {
             rate,
+            rate_field: None,
+            min_rate: None,
+            max_rate: None,
+            adaptive: None,
             key_field,
+            key_field_fallback: Vec::new(),
+            hash_fields: Vec::new(),
             exclude,
+            always_keep: None,
+            size_threshold: None,
+            size_estimator: SampleSizeEstimator::EstimatedJson,
+            hash_function: SampleHashFunction::Seahash,
+            seed: None,
+            decision_cache: None,
+            gap_field: None,
+            bucket_field: None,
+            deterministic: false,
+            pass_ratio: None,
+            no_output: None,
+            multiply_existing: false,
+            annotate_metadata: false,
+            passthrough_until: None,
             count: 0,
+            gap: 0,
+            discarded: PendingDiscards::new(),
         }
     }
-}
 
-impl FunctionTransform for Sample {
-    fn transform(&mut self, output: &mut OutputBuffer, event: Event) {
-        let mut event = {
-            if let Some(condition) = self.exclude.as_ref() {
-                let (result, event) = condition.check(event);
-                if result {
-                    output.push(event);
-                    return;
-                } else {
-                    event
-                }
-            } else {
-                event
-            }
-        };
+    /// Passes events matching `always_keep` without sampling them, like those matching `exclude`.
+    pub fn with_always_keep(mut self, always_keep: Option<Condition>) -> Self {
+        self.always_keep = always_keep;
+        self
+    }
+
+    /// Passes events smaller than `threshold` bytes, as measured by `estimator`, without sampling
+    /// them, when set.
+    pub const fn with_size_threshold(
+        mut self,
+        threshold: Option<usize>,
+        estimator: SampleSizeEstimator,
+    ) -> Self {
+        self.size_threshold = threshold;
+        self.size_estimator = estimator;
+        self
+    }
+
+    /// Samples each event at the rate held in its `rate_field`, when set and valid.
+    pub fn with_rate_field(mut self, rate_field: Option<String>) -> Self {
+        self.rate_field = rate_field;
+        self
+    }
+
+    /// Hashes the first of the `key_field_fallback` fields present on events without `key_field`.
+    pub fn with_key_field_fallback(mut self, key_field_fallback: Vec<String>) -> Self {
+        self.key_field_fallback = key_field_fallback;
+        self
+    }
+
+    /// Hashes the values of `hash_fields` together on events without a key field, when set.
+    pub fn with_hash_fields(mut self, hash_fields: Vec<String>) -> Self {
+        self.hash_fields = hash_fields;
+        self
+    }
+
+    /// Clamps the rate to the `min_rate` and `max_rate` bounds, when set.
+    pub const fn with_rate_bounds(mut self, min_rate: Option<u64>, max_rate: Option<u64>) -> Self {
+        self.min_rate = min_rate;
+        self.max_rate = max_rate;
+        self
+    }
+
+    /// Adapts the rate to pass `target_rate` events per second, when set.
+    pub fn with_target_rate(mut self, target_rate: Option<u64>) -> Self {
+        self.adaptive = target_rate.map(AdaptiveRate::new);
+        self
+    }
+
+    /// Hashes key field values, and whole events when deterministic, with `hash_function`.
+    pub const fn with_hash_function(mut self, hash_function: SampleHashFunction) -> Self {
+        self.hash_function = hash_function;
+        self
+    }
+
+    /// Mixes `seed` into the hash of key field values and whole events.
+    pub const fn with_seed(mut self, seed: Option<u64>) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Caches the buckets of the `size` most recently seen key values, when set.
+    pub fn with_decision_cache_size(mut self, size: Option<NonZeroUsize>) -> Self {
+        self.decision_cache = size.map(DecisionCache::new);
+        self
+    }
+
+    /// Records the number of events discarded since the previous pass in `gap_field`.
+    pub fn with_gap_field(mut self, gap_field: Option<String>) -> Self {
+        self.gap_field = gap_field;
+        self
+    }
+
+    /// Records the hash bucket of sampled events in `bucket_field`.
+    pub fn with_bucket_field(mut self, bucket_field: Option<String>) -> Self {
+        self.bucket_field = bucket_field;
+        self
+    }
+
+    /// Hashes the whole event, instead of count rating, when no key field value is available.
+    pub const fn with_deterministic(mut self, deterministic: bool) -> Self {
+        self.deterministic = deterministic;
+        self
+    }
+
+    /// Emits the ratio of sampled events that were passed at the end of each `window`.
+    pub fn with_pass_ratio_window(mut self, window: Option<Duration>) -> Self {
+        self.pass_ratio = window.map(|window| {
+            WindowedCounter::new(window, NonZeroUsize::new(2).expect("2 is not zero"))
+        });
+        self
+    }
+
+    /// Warns at the end of each `window` in which all sampled events were discarded.
+    pub fn with_no_output_window(mut self, window: Option<Duration>) -> Self {
+        self.no_output = window.map(NoOutputWindow::new);
+        self
+    }
+
+    /// Multiplies the rate by the `sample_rate` of events that were already sampled.
+    pub const fn with_multiply_existing(mut self, multiply_existing: bool) -> Self {
+        self.multiply_existing = multiply_existing;
+        self
+    }
+
+    /// Annotates sampled events in their metadata rather than in the `sample_rate` field.
+    pub const fn with_annotate_metadata(mut self, annotate_metadata: bool) -> Self {
+        self.annotate_metadata = annotate_metadata;
+        self
+    }
+
+    /// Passes all events without sampling them until `grace` has elapsed from now, when set.
+    pub fn with_passthrough_grace(mut self, grace: Option<Duration>) -> Self {
+        self.passthrough_until = grace.map(|grace| Instant::now() + grace);
+        self
+    }
+
+    /// Returns the most memory that the sampling state can take, given the configured windows and
+    /// cache size.
+    ///
+    /// The windowed counters and the decision cache hold a fixed number of keys, so the estimate
+    /// does not grow with the cardinality of `key_field`. The contents of the cached key values
+    /// are not accounted for.
+    pub fn estimated_state_bytes(&self) -> usize {
+        let heap_bytes = [
+            self.pass_ratio
+                .as_ref()
+                .map(WindowedCounter::estimated_heap_bytes),
+            self.no_output
+                .as_ref()
+                .map(|no_output| no_output.0.estimated_heap_bytes()),
+            self.adaptive
+                .as_ref()
+                .map(|adaptive| adaptive.seen.estimated_heap_bytes()),
+            self.decision_cache
+                .as_ref()
+                .map(DecisionCache::estimated_heap_bytes),
+        ]
+        .into_iter()
+        .flatten()
+        .sum::<usize>();
+        mem::size_of::<Self>() + heap_bytes
+    }
 
-        let value = self
-            .key_field
+    /// Returns the rate at which the event is sampled, within the configured bounds: the positive
+    /// rate held in its `rate_field`, or else the configured rate.
+    fn effective_rate(&self, event: &Event) -> u64 {
+        let rate = self
+            .rate_field
             .as_ref()
-            .and_then(|key_field| match &event {
+            .and_then(|rate_field| match event {
+                Event::Log(event) => event.get(rate_field.as_str()),
+                Event::Trace(event) => event.get(rate_field.as_str()),
+                Event::Metric(_) => panic!("component can never receive metric events"),
+            })
+            .and_then(parse_rate)
+            .filter(|rate| *rate > 0)
+            .unwrap_or(self.rate);
+        let rate = self.min_rate.map_or(rate, |min_rate| rate.max(min_rate));
+        self.max_rate.map_or(rate, |max_rate| rate.min(max_rate))
+    }
+
+    /// Returns the value of `key_field`, or else of the first `key_field_fallback` field, that is
+    /// present on the event.
+    fn key_value<'a>(&self, event: &'a Event) -> Option<&'a Value> {
+        self.key_field
+            .iter()
+            .chain(&self.key_field_fallback)
+            .find_map(|key_field| match event {
                 Event::Log(event) => event.get(key_field.as_str()),
                 Event::Trace(event) => event.get(key_field.as_str()),
                 Event::Metric(_) => panic!("component can never receive metric events"),
             })
-            .map(|v| v.to_string_lossy());
+    }
 
-        let num = if let Some(value) = value {
-            seahash::hash(value.as_bytes())
-        } else {
-            self.count
+    fn sample_rate(&self, existing: Option<&Value>, rate: u64) -> u64 {
+        let existing = existing
+            .filter(|_| self.multiply_existing)
+            .and_then(parse_rate);
+        existing.map_or(rate, |existing| existing.saturating_mul(rate))
+    }
+
+    fn annotate_bucket(&self, event: &mut Event, bucket: Option<Bucket>) {
+        let (Some(bucket_field), Some(bucket)) = (&self.bucket_field, bucket) else {
+            return;
         };
 
-        self.count = (self.count + 1) % self.rate;
+        match event {
+            Event::Log(event) => event.insert(bucket_field.as_str(), bucket.index),
+            Event::Trace(event) => event.insert(bucket_field.as_str(), bucket.index),
+            Event::Metric(_) => panic!("component can never receive metric events"),
+        };
+    }
+
+    fn record_pass_ratio(&mut self, passed: bool) {
+        let Some(pass_ratio) = &mut self.pass_ratio else {
+            return;
+        };
+
+        let window = pass_ratio.add(passed, Instant::now());
+        if let Some(window) = window.filter(|window| window.total() > 0) {
+            emit!(SamplePassRatio {
+                ratio: window.count(&true) as f64 / window.total() as f64,
+            });
+        }
+    }
+}
+
+/// The bucket that a sampled event fell in, out of `rate` buckets. Events in bucket 0 are passed.
+#[derive(Clone, Copy, Debug)]
+struct Bucket {
+    index: u64,
+    rate: u64,
+}
+
+/// Parses a rate stored on an event, either as an integer or as a string.
+fn parse_rate(value: &Value) -> Option<u64> {
+    match value {
+        Value::Bytes(bytes) => std::str::from_utf8(bytes).ok()?.parse::<u64>().ok(),
+        Value::Integer(rate) => u64::try_from(*rate).ok(),
+        _ => None,
+    }
+}
+
+/// The buckets of recently sampled key values, so that repeated values are not hashed again.
+///
+/// The bucket of a value only depends on the rate, besides the configured hash function and seed,
+/// so a cached bucket is reused as long as the value is sampled at the same rate.
+struct DecisionCache(LruCache<String, (u64, u64)>);
+
+impl DecisionCache {
+    fn new(size: NonZeroUsize) -> Self {
+        Self(LruCache::new(size))
+    }
+
+    /// Returns the bucket of `value` at `rate`, computing it with `hash` if it is not cached.
+    fn bucket(&mut self, value: &str, rate: u64, hash: impl FnOnce() -> u64) -> u64 {
+        if let Some(&(cached_rate, bucket)) = self.0.get(value) {
+            if cached_rate == rate {
+                return bucket;
+            }
+        }
+
+        let bucket = hash();
+        self.0.put(value.to_owned(), (rate, bucket));
+        bucket
+    }
 
-        if num % self.rate == 0 {
-            match event {
-                Event::Log(ref mut event) => event.insert("sample_rate", self.rate.to_string()),
-                Event::Trace(ref mut event) => event.insert("sample_rate", self.rate.to_string()),
+    /// Returns the most heap memory that the cache can take, besides the contents of the values.
+    fn estimated_heap_bytes(&self) -> usize {
+        // Each value is stored with its rate and bucket, linked to its neighbours, and indexed.
+        let entry =
+            mem::size_of::<String>() + mem::size_of::<(u64, u64)>() + 3 * mem::size_of::<usize>();
+        self.0.cap().get() * entry
+    }
+}
+
+impl Clone for DecisionCache {
+    /// Clones start with an empty cache of the same size, as `LruCache` cannot be cloned.
+    fn clone(&self) -> Self {
+        Self::new(self.0.cap())
+    }
+}
+
+/// Tracks how long all sampled events have been discarded for.
+#[derive(Clone, Debug)]
+struct NoOutputWindow(WindowedCounter<()>);
+
+impl NoOutputWindow {
+    fn new(window: Duration) -> Self {
+        Self(WindowedCounter::unkeyed(window))
+    }
+
+    fn passed(&mut self) {
+        self.0.restart(None);
+    }
+
+    /// Counts an event discarded at `now`. Returns the event to report when this event ends a
+    /// window in which nothing was passed.
+    fn discarded(&mut self, now: Instant) -> Option<SampleNoOutput> {
+        let window = self.0.add((), now)?;
+        // The window is reported with the event that ends it, and the next one starts afresh.
+        self.0.restart(Some(now));
+        Some(SampleNoOutput {
+            discarded: window.total() + 1,
+            window: self.0.window(),
+        })
+    }
+}
+
+/// Measures the throughput of sampled events to find the rate that passes `target_rate` events
+/// per second.
+#[derive(Clone, Debug)]
+struct AdaptiveRate {
+    target_rate: u64,
+    seen: WindowedCounter<()>,
+}
+
+impl AdaptiveRate {
+    fn new(target_rate: u64) -> Self {
+        Self {
+            target_rate,
+            seen: WindowedCounter::unkeyed(ADAPTIVE_WINDOW),
+        }
+    }
+
+    /// Counts an event seen at `now`. Returns the rate for the next window when this event ends
+    /// the current one.
+    fn observe(&mut self, now: Instant) -> Option<u64> {
+        let window = self.seen.add((), now)?;
+        let throughput = window.total() as f64 / window.elapsed.as_secs_f64();
+        Some(((throughput / self.target_rate as f64).ceil() as u64).max(1))
+    }
+}
+
+impl Sample {
+    /// Decides whether the event is passed or discarded, and updates the sampling state
+    /// accordingly, without modifying the event.
+    ///
+    /// The event is returned alongside the decision, as the `exclude` condition takes it by value.
+    pub fn decide(&mut self, event: Event) -> (SampleDecision, Event) {
+        let (decision, _, event) = self.decide_bucketed(event);
+        (decision, event)
+    }
+
+    /// Decides like `decide`, also returning the bucket that sampled events fell in.
+    fn decide_bucketed(&mut self, event: Event) -> (SampleDecision, Option<Bucket>, Event) {
+        if let Some(until) = self.passthrough_until {
+            if Instant::now() < until {
+                return (
+                    SampleDecision::Pass {
+                        via: PassVia::Passthrough,
+                    },
+                    None,
+                    event,
+                );
+            }
+            self.passthrough_until = None;
+        }
+
+        let mut event = event;
+        for condition in self.always_keep.iter().chain(&self.exclude) {
+            let (result, checked) = condition.check(event);
+            if result {
+                return (
+                    SampleDecision::Pass {
+                        via: PassVia::Condition,
+                    },
+                    None,
+                    checked,
+                );
+            }
+            event = checked;
+        }
+
+        if let Some(threshold) = self.size_threshold {
+            if self.size_estimator.size_of(&event) < threshold {
+                return (
+                    SampleDecision::Pass {
+                        via: PassVia::Condition,
+                    },
+                    None,
+                    event,
+                );
+            }
+        }
+
+        let value = self.key_value(&event).map(|v| v.to_string_lossy());
+
+        if let Some(rate) = self
+            .adaptive
+            .as_mut()
+            .and_then(|adaptive| adaptive.observe(Instant::now()))
+        {
+            self.rate = rate;
+        }
+
+        let rate = self.effective_rate(&event);
+        // The count is kept for the rate of the previous event, which can differ from this one.
+        let count = self.count % rate;
+        let index = if let Some(value) = value {
+            let (hash_function, seed) = (self.hash_function, self.seed);
+            let hash = || hash_function.bucket(value.as_bytes(), rate, seed);
+            match &mut self.decision_cache {
+                Some(cache) => cache.bucket(&value, rate, hash),
+                None => hash(),
+            }
+        } else if !self.hash_fields.is_empty() {
+            let values = self
+                .hash_fields
+                .iter()
+                .map(|field| {
+                    match &event {
+                        Event::Log(event) => event.get(field.as_str()),
+                        Event::Trace(event) => event.get(field.as_str()),
+                        Event::Metric(_) => panic!("component can never receive metric events"),
+                    }
+                    .unwrap_or(&Value::Null)
+                })
+                .collect::<Vec<_>>();
+            let bytes = serde_json::to_vec(&values).expect("value is always serializable");
+            self.hash_function.bucket(&bytes, rate, self.seed)
+        } else if self.deterministic {
+            let value = match &event {
+                Event::Log(event) => event.value(),
+                Event::Trace(event) => event.value(),
                 Event::Metric(_) => panic!("component can never receive metric events"),
             };
-            output.push(event);
+            let bytes = serde_json::to_vec(value).expect("value is always serializable");
+            self.hash_function.bucket(&bytes, rate, self.seed)
         } else {
-            emit!(SampleEventDiscarded);
+            count
+        };
+
+        self.count = (count + 1) % rate;
+
+        let passed = index == 0;
+        self.record_pass_ratio(passed);
+        if let Some(no_output) = &mut self.no_output {
+            if passed {
+                no_output.passed();
+            } else if let Some(report) = no_output.discarded(Instant::now()) {
+                emit!(report);
+            }
+        }
+
+        let decision = if passed {
+            SampleDecision::Pass {
+                via: PassVia::Sampling,
+            }
+        } else {
+            SampleDecision::Drop
+        };
+        (decision, Some(Bucket { index, rate }), event)
+    }
+
+    /// Returns whether the event passes, along with the event, annotated with the sampling rate if
+    /// it passes. Events matching the `exclude` condition are always passed as-is.
+    fn sample(&mut self, event: Event) -> (bool, Event) {
+        let (decision, bucket, mut event) = self.decide_bucketed(event);
+        match decision {
+            SampleDecision::Pass {
+                via: PassVia::Condition,
+            } => {
+                emit!(SampleEventExcluded);
+                return (true, event);
+            }
+            SampleDecision::Pass {
+                via: PassVia::Passthrough,
+            } => return (true, event),
+            SampleDecision::Pass {
+                via: PassVia::Sampling,
+            } => {}
+            SampleDecision::Drop => {
+                self.gap += 1;
+                self.annotate_bucket(&mut event, bucket);
+                return (false, event);
+            }
+        }
+
+        self.annotate_bucket(&mut event, bucket);
+
+        let rate = bucket.expect("sampled events have a bucket").rate;
+        if self.annotate_metadata {
+            let metadata = event.metadata_mut().value_mut();
+            let rate = self.sample_rate(metadata.get(path!("sample", "rate")), rate);
+            metadata.insert(path!("sample", "rate"), rate);
+            metadata.insert(path!("sample", "sampled"), true);
+        }
+        match event {
+            Event::Log(ref mut event) => {
+                if !self.annotate_metadata {
+                    let rate = self.sample_rate(event.get("sample_rate"), rate);
+                    event.insert("sample_rate", rate.to_string());
+                }
+                if let Some(gap_field) = &self.gap_field {
+                    event.insert(gap_field.as_str(), self.gap);
+                }
+            }
+            Event::Trace(ref mut event) => {
+                if !self.annotate_metadata {
+                    let rate = self.sample_rate(event.get("sample_rate"), rate);
+                    event.insert("sample_rate", rate.to_string());
+                }
+                if let Some(gap_field) = &self.gap_field {
+                    event.insert(gap_field, self.gap);
+                }
+            }
+            Event::Metric(_) => panic!("component can never receive metric events"),
+        };
+        self.gap = 0;
+        emit!(SampleEventPassed);
+        (true, event)
+    }
+}
+
+impl FunctionTransform for Sample {
+    fn transform(&mut self, output: &mut OutputBuffer, event: Event) {
+        match self.sample(event) {
+            (true, event) => {
+                self.discarded.report();
+                output.push(event);
+            }
+            (false, _) => self.discarded.add(),
+        }
+    }
+}
+
+/// Discarded events that have not been reported yet.
+///
+/// Reporting each discarded event on its own is costly at high rates, so they are reported in
+/// batches: whenever an event is passed, once `DISCARDED_REPORT_COUNT` are pending, once the
+/// oldest of them has been pending for `DISCARDED_REPORT_INTERVAL`, and when the transform is
+/// dropped on shutdown. The interval is checked as events are discarded, so events pending when
+/// the input goes idle are reported with the next event, or on shutdown.
+#[derive(Debug)]
+struct PendingDiscards {
+    count: usize,
+    since: Option<Instant>,
+}
+
+impl PendingDiscards {
+    const fn new() -> Self {
+        Self {
+            count: 0,
+            since: None,
+        }
+    }
+
+    fn add(&mut self) {
+        self.add_at(Instant::now());
+    }
+
+    fn add_at(&mut self, now: Instant) {
+        let since = *self.since.get_or_insert(now);
+        self.count += 1;
+        if self.count >= DISCARDED_REPORT_COUNT
+            || now.saturating_duration_since(since) >= DISCARDED_REPORT_INTERVAL
+        {
+            self.report();
+        }
+    }
+
+    fn report(&mut self) {
+        if self.count > 0 {
+            emit!(SampleEventDiscarded { count: self.count });
+            self.count = 0;
+        }
+        self.since = None;
+    }
+}
+
+impl Clone for PendingDiscards {
+    /// Clones start with nothing to report, so that no discarded event is reported twice.
+    fn clone(&self) -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for PendingDiscards {
+    fn drop(&mut self) {
+        self.report();
+    }
+}
+
+/// Passes every event to the default output, and the events that the inner `Sample` would have
+/// passed, annotated with the sampling rate, to the `sampled` output.
+#[derive(Clone)]
+pub struct ShadowSample(Sample);
+
+impl ShadowSample {
+    pub const fn new(sample: Sample) -> Self {
+        Self(sample)
+    }
+}
+
+impl SyncTransform for ShadowSample {
+    fn transform(&mut self, event: Event, output: &mut TransformOutputsBuf) {
+        if let (true, sampled) = self.0.sample(event.clone()) {
+            output.push_named(SAMPLED_OUTPUT, sampled);
+        }
+        output.push(event);
+    }
+}
+
+/// Passes the events that the inner `Sample` passes to the default output, and sends the rest to
+/// the `dropped` output instead of discarding them.
+///
+/// If the `dropped` output is not `consumed`, its events are reported as discarded. They are still
+/// sent to it, in case a reload adds a consumer without rebuilding the transform.
+#[derive(Clone)]
+pub struct DroppedOutputSample {
+    sample: Sample,
+    consumed: bool,
+}
+
+impl DroppedOutputSample {
+    pub const fn new(sample: Sample, consumed: bool) -> Self {
+        Self { sample, consumed }
+    }
+}
+
+impl SyncTransform for DroppedOutputSample {
+    fn transform(&mut self, event: Event, output: &mut TransformOutputsBuf) {
+        match self.sample.sample(event) {
+            (true, sampled) => {
+                self.sample.discarded.report();
+                output.push(sampled);
+            }
+            (false, dropped) => {
+                if !self.consumed {
+                    self.sample.discarded.add();
+                }
+                output.push_named(DROPPED_OUTPUT, dropped);
+            }
         }
     }
 }
@@ -137,6 +1299,11 @@ impl FunctionTransform for Sample {
 #[cfg(test)]
 mod tests {
     use approx::assert_relative_eq;
+    use lookup::owned_value_path;
+    use rand::{
+        prelude::{SliceRandom, StdRng},
+        SeedableRng,
+    };
 
     use super::*;
     use crate::{
@@ -148,6 +1315,7 @@ mod tests {
     };
     use tokio::sync::mpsc;
     use tokio_stream::wrappers::ReceiverStream;
+    use value::Kind;
 
     fn condition_contains(key: &str, needle: &str) -> Condition {
         let vrl_config = VrlConfig {
@@ -160,11 +1328,55 @@ mod tests {
             .expect("should not fail to build VRL condition")
     }
 
+    impl Sample {
+        /// Returns whether this sampler and `other` pass the same events out of `events`, starting
+        /// from their current state. Neither sampler is modified.
+        fn equivalent_over(&self, other: &Sample, events: &[Event]) -> bool {
+            let survivors = |sample: &Sample| {
+                let mut sample = sample.clone();
+                events
+                    .iter()
+                    .map(|event| sample.sample(event.clone()).0)
+                    .collect::<Vec<_>>()
+            };
+            survivors(self) == survivors(other)
+        }
+    }
+
     #[test]
     fn generate_config() {
         crate::test_util::test_generate_config::<SampleConfig>();
     }
 
+    #[test]
+    fn detects_key_field_missing_from_schema() {
+        let config = |key_field: Option<&str>| SampleConfig {
+            key_field: key_field.map(Into::into),
+            ..toml::from_str("rate = 10").unwrap()
+        };
+        let definition = schema::Definition::empty_legacy_namespace().with_event_field(
+            &owned_value_path!("user_id"),
+            Kind::bytes(),
+            None,
+        );
+
+        assert_eq!(config(None).key_field_missing_from(&definition), None);
+        assert_eq!(
+            config(Some("user_id")).key_field_missing_from(&definition),
+            None
+        );
+        assert_eq!(
+            config(Some("userId")).key_field_missing_from(&definition),
+            Some("userId")
+        );
+
+        // Schemas that allow unknown fields can't rule the field out.
+        assert_eq!(
+            config(Some("userId")).key_field_missing_from(&schema::Definition::any()),
+            None
+        );
+    }
+
     #[test]
     fn hash_samples_at_roughly_the_configured_rate() {
         let num_events = 10000;
@@ -256,6 +1468,134 @@ mod tests {
         }
     }
 
+    #[test]
+    fn decides_without_modifying_events() {
+        let mut sampler = Sample::new(2, None, Some(condition_contains("keep", "yes")));
+        let mut kept = LogEvent::from("kept");
+        kept.insert("keep", "yes");
+
+        let decisions = [
+            kept,
+            LogEvent::from("first"),
+            LogEvent::from("second"),
+            LogEvent::from("third"),
+        ]
+        .into_iter()
+        .map(|event| {
+            let event = Event::from(event);
+            let (decision, decided) = sampler.decide(event.clone());
+            assert_eq!(decided, event);
+            decision
+        })
+        .collect::<Vec<_>>();
+
+        assert_eq!(
+            decisions,
+            vec![
+                SampleDecision::Pass {
+                    via: PassVia::Condition
+                },
+                SampleDecision::Pass {
+                    via: PassVia::Sampling
+                },
+                SampleDecision::Drop,
+                SampleDecision::Pass {
+                    via: PassVia::Sampling
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn estimates_state_bytes_from_configured_windows() {
+        let sampler = Sample::new(10, Some("trace_id".into()), None);
+        let base = sampler.estimated_state_bytes();
+        assert!(base >= mem::size_of::<Sample>());
+
+        let window = Some(Duration::from_secs(1));
+        let with_pass_ratio = sampler.clone().with_pass_ratio_window(window);
+        let with_all = with_pass_ratio
+            .clone()
+            .with_no_output_window(window)
+            .with_target_rate(Some(100));
+        assert!(with_pass_ratio.estimated_state_bytes() > base);
+        assert!(with_all.estimated_state_bytes() > with_pass_ratio.estimated_state_bytes());
+
+        let cached = |size| {
+            sampler
+                .clone()
+                .with_decision_cache_size(NonZeroUsize::new(size))
+                .estimated_state_bytes()
+        };
+        assert!(cached(10) > base);
+        assert_eq!(cached(100) - base, 10 * (cached(10) - base));
+
+        // Sampling many distinct keys does not grow the estimate.
+        let mut sampler = with_all.with_decision_cache_size(NonZeroUsize::new(10));
+        let estimate = sampler.estimated_state_bytes();
+        for i in 0..100 {
+            let mut log = LogEvent::from("span");
+            log.insert("trace_id", format!("trace-{i}"));
+            transform_one(&mut sampler, log.into());
+        }
+        assert_eq!(sampler.estimated_state_bytes(), estimate);
+    }
+
+    #[test]
+    fn passes_events_through_after_reload() {
+        let passed = |sampler: &mut Sample, events: Vec<Event>| {
+            events
+                .into_iter()
+                .filter_map(|event| transform_one(sampler, event))
+                .map(|event| event.as_log().get("sample_rate").is_some())
+                .collect::<Vec<_>>()
+        };
+
+        let mut sampler = Sample::new(4, None, None);
+        assert_eq!(passed(&mut sampler, random_events(6)), vec![true, true]);
+
+        // The sampler is rebuilt by a reload in the middle of the stream, and passes the events in
+        // flight as-is.
+        let mut sampler =
+            Sample::new(4, None, None).with_passthrough_grace(Some(Duration::from_secs(3600)));
+        assert_eq!(passed(&mut sampler, random_events(6)), vec![false; 6]);
+
+        // Sampling resumes once the grace period is over.
+        sampler.passthrough_until = Some(Instant::now() - Duration::from_secs(1));
+        assert_eq!(passed(&mut sampler, random_events(6)), vec![true, true]);
+        assert_eq!(sampler.passthrough_until, None);
+    }
+
+    #[test]
+    fn always_keeps_configured_levels() {
+        let config: SampleConfig =
+            toml::from_str("rate = 10\nalways_keep_levels = [\"error\", \"warn\"]").unwrap();
+        let always_keep = config.always_keep(&Default::default()).unwrap();
+
+        for rate in [2, 10, u64::MAX] {
+            let mut sampler = Sample::new(rate, None, None).with_always_keep(always_keep.clone());
+            let passed = ["info", "error", "debug", "warn", "ERROR"]
+                .into_iter()
+                .cycle()
+                .take(50)
+                .filter_map(|level| {
+                    let mut log = LogEvent::from("event");
+                    log.insert("level", level);
+                    transform_one(&mut sampler, log.into())
+                })
+                .map(|event| event.as_log()["level"].to_string_lossy().into_owned())
+                .filter(|level| level == "error" || level == "warn")
+                .count();
+            assert_eq!(passed, 20, "rate {rate}");
+        }
+
+        let config = SampleConfig {
+            level_field: "severity[".into(),
+            ..config
+        };
+        assert!(config.always_keep(&Default::default()).is_err());
+    }
+
     #[test]
     fn handles_key_field() {
         for key_field in &[None, Some("other_field".into())] {
@@ -339,14 +1679,721 @@ mod tests {
         assert_eq!(total_passed, 1);
     }
 
+    #[test]
+    fn hash_functions_are_pinned() {
+        use SampleHashFunction::{Seahash, Sha256, Xxhash};
+
+        assert_eq!(Seahash.bucket(b"trace-1", 10, None), 2);
+        assert_eq!(Sha256.bucket(b"trace-1", 10, None), 8);
+        assert_eq!(Xxhash.bucket(b"trace-1", 10, None), 9);
+
+        // The remainder of the whole SHA-256 digest of "a", as computed by other systems.
+        assert_eq!(Sha256.bucket(b"a", 1000, None), 499);
+
+        let survivors = |hash_function| seeded_survivors(hash_function, None);
+        assert_eq!(survivors(Seahash), vec!["trace-6"]);
+        assert_eq!(survivors(Sha256), vec!["trace-4"]);
+        assert!(survivors(Xxhash).is_empty());
+    }
+
+    #[test]
+    fn seed_changes_selection() {
+        use SampleHashFunction::{Seahash, Sha256};
+
+        assert_eq!(seeded_survivors(Seahash, Some(2)), vec!["trace-1"]);
+        assert_eq!(seeded_survivors(Sha256, Some(2)), vec!["trace-6"]);
+
+        // The transform's own seed takes precedence over the global one.
+        let config = |seed| SampleConfig {
+            seed,
+            ..toml::from_str("rate = 10").unwrap()
+        };
+        let globals = GlobalOptions {
+            sample_seed: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(config(None).seed(&globals), Some(2));
+        assert_eq!(config(Some(1)).seed(&globals), Some(1));
+        assert_eq!(config(None).seed(&GlobalOptions::default()), None);
+    }
+
+    /// Returns the `trace_id`s, out of `trace-1` to `trace-8`, that pass a rate of 10.
+    fn seeded_survivors(hash_function: SampleHashFunction, seed: Option<u64>) -> Vec<String> {
+        let mut sampler = Sample::new(10, Some("trace_id".into()), None)
+            .with_hash_function(hash_function)
+            .with_seed(seed);
+        (1..=8)
+            .filter_map(|i| {
+                let mut log = LogEvent::from("span");
+                log.insert("trace_id", format!("trace-{i}"));
+                transform_one(&mut sampler, log.into())
+            })
+            .map(|event| event.as_log()["trace_id"].to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn records_hash_bucket_of_sampled_events() {
+        let mut sampler =
+            Sample::new(10, Some("trace_id".into()), None).with_bucket_field(Some("bucket".into()));
+        let mut bucket = |trace_id: &str| {
+            let mut log = LogEvent::from("span");
+            log.insert("trace_id", trace_id);
+            let (passed, event) = sampler.sample(log.into());
+            (passed, event.as_log()["bucket"].clone())
+        };
+
+        for _ in 0..3 {
+            assert_eq!(bucket("trace-1"), (false, Value::Integer(2)));
+            assert_eq!(bucket("trace-6"), (true, Value::Integer(0)));
+        }
+
+        let mut sampler = sampler.with_bucket_field(None);
+        let (_, event) = sampler.sample(LogEvent::from("span").into());
+        assert!(event.as_log().get("bucket").is_none());
+    }
+
+    #[test]
+    fn key_field_falls_back_in_order() {
+        let sampler = Sample::new(10, Some("trace_id".into()), None)
+            .with_key_field_fallback(vec!["request_id".into(), "session_id".into()]);
+        let key = |fields: &[&str]| {
+            let mut log = LogEvent::from("span");
+            for field in fields {
+                log.insert(*field, *field);
+            }
+            sampler
+                .key_value(&log.into())
+                .map(|value| value.to_string_lossy().into_owned())
+        };
+
+        assert_eq!(
+            key(&["trace_id", "request_id", "session_id"]).as_deref(),
+            Some("trace_id")
+        );
+        assert_eq!(
+            key(&["request_id", "session_id"]).as_deref(),
+            Some("request_id")
+        );
+        assert_eq!(key(&["session_id"]).as_deref(), Some("session_id"));
+        assert_eq!(key(&[]), None);
+
+        // Events without any of the fields are count rated.
+        let mut sampler = sampler;
+        let passed = random_events(20)
+            .into_iter()
+            .filter_map(|event| transform_one(&mut sampler, event))
+            .count();
+        assert_eq!(passed, 2);
+    }
+
+    #[test]
+    fn hash_fields_share_decisions_between_identical_values() {
+        let mut sampler =
+            Sample::new(4, None, None).with_hash_fields(vec!["user".into(), "action".into()]);
+
+        let mut passed = std::collections::BTreeSet::new();
+        for i in 0..100 {
+            let mut log = LogEvent::from(format!("attempt {i}"));
+            log.insert("user", format!("u{}", i % 20));
+            log.insert("action", "login");
+            if let Some(event) = transform_one(&mut sampler, log.into()) {
+                passed.insert(event.as_log()["user"].to_string_lossy().into_owned());
+            }
+        }
+
+        // Every attempt by a passed user is passed, whatever its message.
+        assert_eq!(passed.into_iter().collect::<Vec<_>>(), vec!["u0", "u8"]);
+    }
+
+    #[test]
+    fn compares_sampling_behavior_of_configs() {
+        let events = (0..200)
+            .map(|i| {
+                let mut log = LogEvent::from(format!("span {i}"));
+                if i % 3 > 0 {
+                    log.insert("trace_id", format!("trace-{}", i % 40));
+                }
+                Event::from(log)
+            })
+            .collect::<Vec<_>>();
+        let by_trace_id = Sample::new(4, Some("trace_id".into()), None);
+
+        // Migrating the key field to the fallbacks, or the rate to a lower bound, keeps the same
+        // events.
+        let migrated = Sample::new(4, None, None).with_key_field_fallback(vec!["trace_id".into()]);
+        assert!(by_trace_id.equivalent_over(&migrated, &events));
+        let migrated =
+            Sample::new(2, Some("trace_id".into()), None).with_rate_bounds(Some(4), None);
+        assert!(by_trace_id.equivalent_over(&migrated, &events));
+
+        // Seeding the hash keeps the rate, but not the events.
+        let seeded = Sample::new(4, Some("trace_id".into()), None).with_seed(Some(1));
+        assert!(!by_trace_id.equivalent_over(&seeded, &events));
+    }
+
+    #[test]
+    fn decision_cache_reuses_buckets() {
+        let mut cache = DecisionCache::new(NonZeroUsize::new(2).unwrap());
+        assert_eq!(cache.bucket("a", 4, || 1), 1);
+        assert_eq!(cache.bucket("a", 4, || unreachable!("bucket is cached")), 1);
+
+        // A different rate changes the bucket, and the least recently used value is evicted.
+        assert_eq!(cache.bucket("a", 10, || 7), 7);
+        assert_eq!(cache.bucket("b", 10, || 3), 3);
+        assert_eq!(cache.bucket("c", 10, || 0), 0);
+        assert_eq!(cache.bucket("a", 10, || 5), 5);
+
+        // Caching does not change which events are passed.
+        let events = (0..200)
+            .map(|i| {
+                let mut log = LogEvent::from(format!("span {i}"));
+                log.insert("user_id", format!("user-{}", i % 7));
+                log.insert("rate", if i % 20 < 10 { 2_i64 } else { 5_i64 });
+                Event::from(log)
+            })
+            .collect::<Vec<_>>();
+        let sampler =
+            Sample::new(4, Some("user_id".into()), None).with_rate_field(Some("rate".into()));
+        let cached = sampler
+            .clone()
+            .with_decision_cache_size(NonZeroUsize::new(3));
+        assert!(sampler.equivalent_over(&cached, &events));
+    }
+
+    #[test]
+    fn passes_events_below_size_threshold() {
+        let log = |message: &str| Event::from(LogEvent::from(message));
+        let small = log("ok");
+        let large = log(&"x".repeat(200));
+
+        for estimator in [
+            SampleSizeEstimator::EstimatedJson,
+            SampleSizeEstimator::Json,
+        ] {
+            assert!(estimator.size_of(&small) < 100);
+            assert!(estimator.size_of(&large) >= 200);
+
+            let mut sampler = Sample::new(4, None, None).with_size_threshold(Some(100), estimator);
+            for _ in 0..8 {
+                assert_eq!(
+                    sampler.decide(small.clone()).0,
+                    SampleDecision::Pass {
+                        via: PassVia::Condition
+                    }
+                );
+            }
+
+            let passed = (0..8)
+                .filter(|_| matches!(sampler.decide(large.clone()).0, SampleDecision::Pass { .. }))
+                .count();
+            assert_eq!(passed, 2);
+        }
+    }
+
+    #[test]
+    fn clamps_rate_to_bounds() {
+        let annotated_rates = |rate, min_rate, max_rate| {
+            let mut sampler = Sample::new(rate, None, None).with_rate_bounds(min_rate, max_rate);
+            random_events(20)
+                .into_iter()
+                .filter_map(|event| transform_one(&mut sampler, event))
+                .map(|event| event.as_log()["sample_rate"].to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+        };
+
+        // Raised to the lower bound.
+        assert_eq!(annotated_rates(2, Some(5), Some(10)), vec!["5"; 4]);
+        // Lowered to the upper bound.
+        assert_eq!(annotated_rates(20, Some(5), Some(10)), vec!["10"; 2]);
+        // Within the bounds, or unbounded.
+        assert_eq!(annotated_rates(4, Some(2), Some(10)), vec!["4"; 5]);
+        assert_eq!(annotated_rates(4, None, None), vec!["4"; 5]);
+    }
+
+    #[test]
+    fn reads_rate_from_event_field() {
+        let mut sampler =
+            Sample::new(10, None, None).with_rate_field(Some("tenant.sample_rate".into()));
+        let mut annotated_rates = |rate: Option<Value>| {
+            (0..10)
+                .filter_map(|i| {
+                    let mut log = LogEvent::from(format!("event {i}"));
+                    if let Some(rate) = &rate {
+                        log.insert("tenant.sample_rate", rate.clone());
+                    }
+                    transform_one(&mut sampler, log.into())
+                })
+                .map(|event| event.as_log()["sample_rate"].to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(annotated_rates(Some(Value::from(2))), vec!["2"; 5]);
+        assert_eq!(annotated_rates(Some(Value::from("5"))), vec!["5"; 2]);
+        // Missing, non-numeric, and zero rates fall back to the configured rate.
+        assert_eq!(annotated_rates(None), vec!["10"]);
+        assert_eq!(annotated_rates(Some(Value::from("often"))), vec!["10"]);
+        assert_eq!(annotated_rates(Some(Value::from(0))), vec!["10"]);
+
+        // The rate read from the event is kept within the bounds.
+        let mut sampler = Sample::new(10, None, None)
+            .with_rate_field(Some("rate".into()))
+            .with_rate_bounds(Some(3), None);
+        let mut log = LogEvent::from("event");
+        log.insert("rate", 2);
+        let passing = transform_one(&mut sampler, log.into()).unwrap();
+        assert_eq!(passing.as_log()["sample_rate"], "3".into());
+    }
+
+    #[test]
+    fn count_buckets_stay_within_event_rate() {
+        let mut sampler = Sample::new(10, None, None)
+            .with_rate_field(Some("rate".into()))
+            .with_bucket_field(Some("bucket".into()));
+        let mut sample = |rate: i64| {
+            let mut log = LogEvent::from("event");
+            log.insert("rate", rate);
+            let (passed, event) = sampler.sample(log.into());
+            (passed, event.as_log()["bucket"].clone(), event)
+        };
+
+        for _ in 0..5 {
+            sample(10);
+        }
+
+        // The count reached at a rate of 10 is reduced to the lower rate of the next events.
+        let (passed, bucket, _) = sample(2);
+        assert_eq!((passed, bucket), (false, Value::Integer(1)));
+        let (passed, bucket, event) = sample(2);
+        assert_eq!((passed, bucket), (true, Value::Integer(0)));
+        assert_eq!(event.as_log()["sample_rate"], "2".into());
+    }
+
+    #[test]
+    fn adaptive_rate_follows_throughput() {
+        let start = Instant::now();
+        let mut adaptive = AdaptiveRate::new(100);
+        let mut elapsed = Duration::ZERO;
+        let mut rates = Vec::new();
+
+        // Bursts of 1000, 50, and 5000 events, each spread evenly over a second.
+        for events in [1000_u32, 50, 5000, 1000] {
+            let step = ADAPTIVE_WINDOW / events;
+            for _ in 0..events {
+                rates.extend(adaptive.observe(start + elapsed));
+                elapsed += step;
+            }
+        }
+
+        assert_eq!(rates, vec![10, 1, 50]);
+    }
+
+    #[test]
+    fn adaptive_rate_stays_within_bounds() {
+        let mut sampler = Sample::new(1, None, None)
+            .with_target_rate(Some(100))
+            .with_rate_bounds(None, Some(20));
+        let adaptive = sampler.adaptive.as_mut().unwrap();
+        let start = Instant::now() - ADAPTIVE_WINDOW;
+        for _ in 0..5000 {
+            assert!(adaptive.observe(start).is_none());
+        }
+
+        let passing = transform_one(&mut sampler, random_events(1).remove(0)).unwrap();
+        assert_eq!(sampler.rate, 50);
+        assert_eq!(passing.as_log()["sample_rate"], "20".into());
+    }
+
+    #[tokio::test]
+    async fn rejects_inverted_rate_bounds() {
+        let config = SampleConfig {
+            min_rate: Some(20),
+            max_rate: Some(5),
+            ..toml::from_str("rate = 10").unwrap()
+        };
+        assert!(config.build(&TransformContext::default()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn rejects_zero_rate_bounds() {
+        let config: SampleConfig = toml::from_str("rate = 10\nmax_rate = 0").unwrap();
+        let error = config.build(&TransformContext::default()).await.err();
+        assert_eq!(
+            error.map(|error| error.to_string()).as_deref(),
+            Some("`max_rate` must be greater than 0")
+        );
+
+        let config: SampleConfig = toml::from_str("rate = 10\nmin_rate = 0").unwrap();
+        let error = config.build(&TransformContext::default()).await.err();
+        assert_eq!(
+            error.map(|error| error.to_string()).as_deref(),
+            Some("`min_rate` must be greater than 0")
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_zero_rate() {
+        let config: SampleConfig = toml::from_str("rate = 0").unwrap();
+        let error = config.build(&TransformContext::default()).await.err();
+        assert_eq!(
+            error.map(|error| error.to_string()).as_deref(),
+            Some("`rate` must be greater than 0, unless `min_rate` is set")
+        );
+
+        let config: SampleConfig = toml::from_str("rate = 0\nmin_rate = 5").unwrap();
+        assert!(config.build(&TransformContext::default()).await.is_ok());
+    }
+
+    #[test]
+    fn warns_about_fixed_rate_of_one() {
+        let warnings = |config: &str| toml::from_str::<SampleConfig>(config).unwrap().warnings();
+        let passes_all = vec!["passes all events, as its sample rate is always 1".to_owned()];
+
+        assert_eq!(warnings("rate = 1"), passes_all);
+        assert_eq!(warnings("rate = 10\nmax_rate = 1"), passes_all);
+        assert_eq!(
+            warnings("rate = 10\nrate_field = \"rate\"\nmax_rate = 1"),
+            passes_all
+        );
+        assert_eq!(warnings("rate = 0\nmin_rate = 1"), passes_all);
+
+        assert!(warnings("rate = 10").is_empty());
+        assert!(warnings("rate = 1\nmin_rate = 5").is_empty());
+        assert!(warnings("rate = 1\nrate_field = \"rate\"").is_empty());
+        assert!(warnings("rate = 1\ntarget_rate = 100").is_empty());
+    }
+
+    #[test]
+    fn warns_about_unused_decision_cache() {
+        let warnings = |config: &str| toml::from_str::<SampleConfig>(config).unwrap().warnings();
+        let ignored = vec![
+            "ignores `decision_cache_size`, as only the `sha256` hash function uses it".to_owned(),
+        ];
+
+        assert_eq!(warnings("rate = 10\ndecision_cache_size = 64"), ignored);
+        assert_eq!(
+            warnings("rate = 10\ndecision_cache_size = 64\nhash_function = \"xxhash\""),
+            ignored
+        );
+        assert!(
+            warnings("rate = 10\ndecision_cache_size = 64\nhash_function = \"sha256\"").is_empty()
+        );
+    }
+
+    #[test]
+    fn parses_window_durations() {
+        let config: SampleConfig = toml::from_str(
+            r#"
+            rate = 10
+            pass_ratio_window = "1m"
+            no_output_window = "5m 30s"
+            passthrough_on_reload_gap = "500ms"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.pass_ratio_window, Some(Duration::from_secs(60)));
+        assert_eq!(config.no_output_window, Some(Duration::from_secs(330)));
+        assert_eq!(
+            config.passthrough_on_reload_gap,
+            Some(Duration::from_millis(500))
+        );
+
+        let config: SampleConfig = toml::from_str("rate = 10").unwrap();
+        assert_eq!(config.pass_ratio_window, None);
+
+        let error =
+            toml::from_str::<SampleConfig>("rate = 10\npass_ratio_window = \"5 lightyears\"")
+                .unwrap_err()
+                .to_string();
+        assert!(
+            error.starts_with(r#"invalid duration "5 lightyears": unknown time unit "lightyears""#),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn reports_discarded_events_in_batches() {
+        let event = |keep: &str| {
+            let mut log = LogEvent::from("event");
+            log.insert("keep", keep);
+            Event::from(log)
+        };
+        // Only the first event is passed by the count, and all others are discarded.
+        let mut sampler = Sample::new(u64::MAX, None, Some(condition_contains("keep", "yes")));
+
+        for _ in 0..=1500 {
+            _ = transform_one(&mut sampler, event("no"));
+        }
+        assert_eq!(sampler.discarded.count, 500);
+        assert_eq!(sampler.clone().discarded.count, 0);
+
+        // Passing an event reports the discarded events before it.
+        assert!(transform_one(&mut sampler, event("yes")).is_some());
+        assert_eq!(sampler.discarded.count, 0);
+    }
+
+    #[test]
+    fn reports_discarded_events_after_interval() {
+        let start = Instant::now();
+        let at = |millis| start + Duration::from_millis(millis);
+        let mut discarded = PendingDiscards::new();
+
+        discarded.add_at(at(0));
+        discarded.add_at(at(500));
+        discarded.add_at(at(999));
+        assert_eq!(discarded.count, 3);
+
+        // The events are reported once the oldest has been pending for the interval.
+        discarded.add_at(at(1000));
+        assert_eq!((discarded.count, discarded.since), (0, None));
+
+        // The next interval starts with the next discarded event.
+        discarded.add_at(at(5000));
+        discarded.add_at(at(5999));
+        assert_eq!(discarded.count, 2);
+        discarded.add_at(at(6000));
+        assert_eq!(discarded.count, 0);
+    }
+
+    #[test]
+    fn sampler_adds_gap_to_event() {
+        let mut sampler = Sample::new(3, None, None).with_gap_field(Some("gap".into()));
+        let passing = random_events(7)
+            .into_iter()
+            .filter_map(|event| transform_one(&mut sampler, event))
+            .map(|event| event.as_log()["gap"].as_integer().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(passing, vec![0, 2, 2]);
+    }
+
+    #[test]
+    fn deterministic_samples_independent_of_order() {
+        let events = random_events(1000);
+        let mut shuffled = events.clone();
+        shuffled.shuffle(&mut StdRng::seed_from_u64(0));
+
+        let survivors = |events: Vec<Event>| {
+            let mut sampler = Sample::new(4, None, None).with_deterministic(true);
+            let mut messages = events
+                .into_iter()
+                .filter_map(|event| transform_one(&mut sampler, event))
+                .map(|event| {
+                    event.as_log()[log_schema().message_key()]
+                        .to_string_lossy()
+                        .into_owned()
+                })
+                .collect::<Vec<_>>();
+            messages.sort();
+            messages
+        };
+
+        let first_run = survivors(events);
+        assert!(!first_run.is_empty());
+        assert_eq!(first_run, survivors(shuffled));
+    }
+
+    #[test]
+    fn sampler_tracks_pass_ratio_per_window() {
+        let mut sampler = Sample::new(4, None, None).with_pass_ratio_window(Some(Duration::ZERO));
+        for event in random_events(8) {
+            _ = transform_one(&mut sampler, event);
+        }
+        // With an empty window, each event starts a new window.
+        assert_eq!(sampler.pass_ratio.as_ref().unwrap().total(), 1);
+
+        let mut sampler =
+            Sample::new(4, None, None).with_pass_ratio_window(Some(Duration::from_secs(3600)));
+        for event in random_events(8) {
+            _ = transform_one(&mut sampler, event);
+        }
+        let pass_ratio = sampler.pass_ratio.as_ref().unwrap();
+        assert_eq!(pass_ratio.total(), 8);
+        assert_eq!(pass_ratio.count(&true), 2);
+    }
+
+    #[test]
+    fn reports_windows_without_output() {
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        let mut no_output = NoOutputWindow::new(Duration::from_secs(10));
+
+        assert!(no_output.discarded(at(0)).is_none());
+        assert!(no_output.discarded(at(5)).is_none());
+        let report = no_output.discarded(at(10)).unwrap();
+        assert_eq!(report.discarded, 3);
+
+        // Passing an event restarts the window.
+        assert!(no_output.discarded(at(11)).is_none());
+        no_output.passed();
+        assert!(no_output.discarded(at(15)).is_none());
+        assert!(no_output.discarded(at(24)).is_none());
+        assert_eq!(no_output.discarded(at(25)).unwrap().discarded, 3);
+    }
+
+    #[test]
+    fn multiplies_existing_sample_rate() {
+        let two_stage = |multiply_existing: bool| {
+            let mut first = Sample::new(2, None, None).with_multiply_existing(multiply_existing);
+            let mut second = Sample::new(5, None, None).with_multiply_existing(multiply_existing);
+            random_events(100)
+                .into_iter()
+                .filter_map(|event| transform_one(&mut first, event))
+                .filter_map(|event| transform_one(&mut second, event))
+                .map(|event| event.as_log()["sample_rate"].clone())
+                .collect::<Vec<_>>()
+        };
+
+        let passing = two_stage(true);
+        assert_eq!(passing.len(), 10);
+        assert!(passing.iter().all(|rate| *rate == "10".into()));
+
+        let passing = two_stage(false);
+        assert_eq!(passing.len(), 10);
+        assert!(passing.iter().all(|rate| *rate == "5".into()));
+
+        for (existing, want) in [(Value::from(4), "12"), (Value::from("often"), "3")] {
+            let mut sampler = Sample::new(3, None, None).with_multiply_existing(true);
+            let mut log = LogEvent::from("already sampled");
+            log.insert("sample_rate", existing);
+            let passing = transform_one(&mut sampler, log.into()).unwrap();
+            assert_eq!(passing.as_log()["sample_rate"], want.into());
+        }
+    }
+
+    #[test]
+    fn annotates_metadata_without_modifying_event() {
+        let mut sampler = Sample::new(2, None, None)
+            .with_multiply_existing(true)
+            .with_annotate_metadata(true);
+        let events = random_events(10);
+        let passing = events
+            .iter()
+            .cloned()
+            .filter_map(|event| transform_one(&mut sampler, event))
+            .collect::<Vec<_>>();
+        assert_eq!(passing.len(), 5);
+        for (event, original) in passing.iter().zip(events.iter().step_by(2)) {
+            assert_eq!(event.as_log().value(), original.as_log().value());
+            let metadata = event.metadata().value();
+            assert_eq!(metadata.get(path!("sample", "rate")), Some(&Value::from(2)));
+            assert_eq!(
+                metadata.get(path!("sample", "sampled")),
+                Some(&Value::from(true))
+            );
+        }
+
+        let mut sampler = Sample::new(3, None, None)
+            .with_multiply_existing(true)
+            .with_annotate_metadata(true);
+        let passing = transform_one(&mut sampler, passing[0].clone()).unwrap();
+        assert_eq!(
+            passing.metadata().value().get(path!("sample", "rate")),
+            Some(&Value::from(6))
+        );
+        assert!(passing.as_log().get("sample_rate").is_none());
+    }
+
+    #[test]
+    fn sends_unsampled_events_to_dropped_output() {
+        let mut sampler = DroppedOutputSample::new(Sample::new(4, None, None), true);
+        let mut outputs = TransformOutputsBuf::new_with_capacity(
+            vec![
+                Output::default(DataType::Log),
+                Output::default(DataType::Log).with_port(DROPPED_OUTPUT),
+            ],
+            1,
+        );
+
+        let events = random_events(8);
+        for event in events.clone() {
+            SyncTransform::transform(&mut sampler, event, &mut outputs);
+        }
+
+        let primary = outputs.take_primary().into_events().collect::<Vec<_>>();
+        assert_eq!(primary.len(), 2);
+        assert!(primary
+            .iter()
+            .all(|event| event.as_log()["sample_rate"] == "4".into()));
+
+        let dropped = outputs
+            .take_all_named()
+            .remove(DROPPED_OUTPUT)
+            .unwrap()
+            .into_events()
+            .collect::<Vec<_>>();
+        let unsampled = events
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| i % 4 != 0)
+            .map(|(_, event)| event)
+            .collect::<Vec<_>>();
+        assert_eq!(dropped, unsampled);
+    }
+
+    #[test]
+    fn counts_events_of_unconsumed_dropped_output_as_discarded() {
+        let outputs = || {
+            TransformOutputsBuf::new_with_capacity(
+                vec![
+                    Output::default(DataType::Log),
+                    Output::default(DataType::Log).with_port(DROPPED_OUTPUT),
+                ],
+                1,
+            )
+        };
+
+        for consumed in [true, false] {
+            let mut sampler = DroppedOutputSample::new(Sample::new(u64::MAX, None, None), consumed);
+            let mut outputs = outputs();
+            for event in random_events(8) {
+                SyncTransform::transform(&mut sampler, event, &mut outputs);
+            }
+
+            let dropped = outputs.take_all_named().remove(DROPPED_OUTPUT).unwrap();
+            assert_eq!(dropped.len(), 7);
+            let discarded = if consumed { 0 } else { 7 };
+            assert_eq!(
+                sampler.sample.discarded.count, discarded,
+                "consumed: {consumed}"
+            );
+        }
+    }
+
+    #[test]
+    fn shadow_passes_all_events_and_samples_to_named_output() {
+        let mut sampler = ShadowSample::new(Sample::new(4, None, None));
+        let mut outputs = TransformOutputsBuf::new_with_capacity(
+            vec![
+                Output::default(DataType::Log),
+                Output::default(DataType::Log).with_port(SAMPLED_OUTPUT),
+            ],
+            1,
+        );
+
+        let events = random_events(8);
+        for event in events.clone() {
+            SyncTransform::transform(&mut sampler, event, &mut outputs);
+        }
+
+        let primary = outputs.take_primary().into_events().collect::<Vec<_>>();
+        assert_eq!(primary, events);
+
+        let sampled = outputs
+            .take_all_named()
+            .remove(SAMPLED_OUTPUT)
+            .unwrap()
+            .into_events()
+            .collect::<Vec<_>>();
+        assert_eq!(sampled.len(), 2);
+        assert!(sampled
+            .iter()
+            .all(|event| event.as_log()["sample_rate"] == "4".into()));
+    }
+
     #[tokio::test]
     async fn emits_internal_events() {
         assert_transform_compliance(async move {
-            let config = SampleConfig {
-                rate: 1,
-                key_field: None,
-                exclude: None,
-            };
+            let config: SampleConfig = toml::from_str("rate = 1").unwrap();
             let (tx, rx) = mpsc::channel(1);
             let (topology, mut out) = create_topology(ReceiverStream::new(rx), config).await;
 
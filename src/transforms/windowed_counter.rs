@@ -0,0 +1,228 @@
+//! Counting of events over fixed windows of time.
+
+use std::{
+    hash::Hash,
+    mem,
+    num::NonZeroUsize,
+    time::{Duration, Instant},
+};
+
+use indexmap::IndexMap;
+
+/// Counts events in consecutive fixed windows of time, separately for each key.
+///
+/// A window starts with the first event counted after the counter is created or restarted, and
+/// ends with the first event at least `window` after its start. That event is counted in the next
+/// window, which starts with it.
+///
+/// At most `max_keys` keys are counted in each window. Counting a new key when that many are
+/// already counted evicts the key that was first counted in the window, so that memory is bounded
+/// regardless of the cardinality of the keys.
+#[derive(Clone, Debug)]
+pub(crate) struct WindowedCounter<K> {
+    window: Duration,
+    max_keys: NonZeroUsize,
+    start: Option<Instant>,
+    counts: IndexMap<K, u64>,
+}
+
+/// The counts of a window that has ended.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct ClosedWindow<K> {
+    /// The time between the start of the window and the event that ended it.
+    pub(crate) elapsed: Duration,
+
+    /// The count of each key in the window, in the order the keys were first counted.
+    pub(crate) counts: Vec<(K, u64)>,
+}
+
+impl<K: PartialEq> ClosedWindow<K> {
+    /// Returns the number of events counted for `key` in the window.
+    pub(crate) fn count(&self, key: &K) -> u64 {
+        self.counts
+            .iter()
+            .find_map(|(k, count)| (k == key).then_some(*count))
+            .unwrap_or(0)
+    }
+
+    /// Returns the number of events counted in the window, for all keys.
+    pub(crate) fn total(&self) -> u64 {
+        self.counts.iter().map(|(_, count)| count).sum()
+    }
+}
+
+impl WindowedCounter<()> {
+    /// Creates a counter of events that are not told apart by key.
+    pub(crate) fn unkeyed(window: Duration) -> Self {
+        Self::new(window, NonZeroUsize::new(1).expect("1 is not zero"))
+    }
+}
+
+impl<K: Eq + Hash> WindowedCounter<K> {
+    pub(crate) fn new(window: Duration, max_keys: NonZeroUsize) -> Self {
+        Self {
+            window,
+            max_keys,
+            start: None,
+            counts: IndexMap::new(),
+        }
+    }
+
+    /// Returns the duration of each window.
+    pub(crate) const fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// Counts an event for `key` at `now`. Returns the counts of the current window if this event
+    /// ends it.
+    pub(crate) fn add(&mut self, key: K, now: Instant) -> Option<ClosedWindow<K>> {
+        let start = *self.start.get_or_insert(now);
+        let elapsed = now.saturating_duration_since(start);
+
+        let closed = (elapsed >= self.window).then(|| {
+            self.start = Some(now);
+            ClosedWindow {
+                elapsed,
+                counts: self.counts.drain(..).collect(),
+            }
+        });
+
+        if !self.counts.contains_key(&key) && self.counts.len() >= self.max_keys.get() {
+            self.counts.shift_remove_index(0);
+        }
+        *self.counts.entry(key).or_insert(0) += 1;
+
+        closed
+    }
+
+    /// Returns the number of events counted for `key` in the current window.
+    pub(crate) fn count(&self, key: &K) -> u64 {
+        self.counts.get(key).copied().unwrap_or(0)
+    }
+
+    /// Returns the number of events counted in the current window, for all keys.
+    pub(crate) fn total(&self) -> u64 {
+        self.counts.values().sum()
+    }
+
+    /// Returns the most heap memory that the counts can take, once `max_keys` keys are counted.
+    pub(crate) fn estimated_heap_bytes(&self) -> usize {
+        // Each key is stored alongside its hash and count, and indexed by its position.
+        let entry = mem::size_of::<K>() + mem::size_of::<u64>() + 2 * mem::size_of::<usize>();
+        self.max_keys.get() * entry
+    }
+
+    /// Discards the counts of the current window, and starts the next one at `now`, or with the
+    /// next event if `now` is `None`.
+    pub(crate) fn restart(&mut self, now: Option<Instant>) {
+        self.start = now;
+        self.counts.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rolls_over_windows() {
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        let mut counter = WindowedCounter::unkeyed(Duration::from_secs(10));
+
+        assert_eq!(counter.add((), at(0)), None);
+        assert_eq!(counter.add((), at(5)), None);
+        assert_eq!(counter.add((), at(9)), None);
+        assert_eq!(counter.count(&()), 3);
+
+        // The event ending the window is counted in the next one.
+        let closed = counter.add((), at(12)).unwrap();
+        assert_eq!(closed.elapsed, Duration::from_secs(12));
+        assert_eq!(closed.total(), 3);
+        assert_eq!(counter.count(&()), 1);
+
+        assert_eq!(counter.add((), at(21)), None);
+        assert_eq!(counter.add((), at(22)).unwrap().total(), 2);
+    }
+
+    #[test]
+    fn restarts_windows() {
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        let mut counter = WindowedCounter::unkeyed(Duration::from_secs(10));
+
+        counter.add((), at(0));
+        counter.restart(None);
+        assert_eq!(counter.count(&()), 0);
+        assert_eq!(counter.add((), at(5)), None);
+        assert_eq!(counter.add((), at(14)), None);
+        assert_eq!(counter.add((), at(15)).unwrap().total(), 2);
+
+        counter.restart(Some(at(20)));
+        assert_eq!(counter.add((), at(29)), None);
+        assert_eq!(counter.add((), at(30)).unwrap().total(), 1);
+    }
+
+    #[test]
+    fn counts_keys_separately() {
+        let start = Instant::now();
+        let mut counter =
+            WindowedCounter::new(Duration::from_secs(1), NonZeroUsize::new(3).unwrap());
+
+        for key in ["a", "b", "a", "c", "a"] {
+            assert_eq!(counter.add(key, start), None);
+        }
+        assert_eq!(counter.count(&"a"), 3);
+        assert_eq!(counter.count(&"b"), 1);
+        assert_eq!(counter.count(&"d"), 0);
+        assert_eq!(counter.total(), 5);
+
+        let closed = counter.add("b", start + Duration::from_secs(1)).unwrap();
+        assert_eq!(closed.counts, vec![("a", 3), ("b", 1), ("c", 1)]);
+        assert_eq!(closed.count(&"b"), 1);
+        assert_eq!(closed.total(), 5);
+        assert_eq!(counter.count(&"a"), 0);
+        assert_eq!(counter.count(&"b"), 1);
+    }
+
+    #[test]
+    fn estimates_heap_bytes_from_max_keys() {
+        let counter = |max_keys| {
+            WindowedCounter::<u64>::new(
+                Duration::from_secs(1),
+                NonZeroUsize::new(max_keys).unwrap(),
+            )
+        };
+
+        let one = counter(1).estimated_heap_bytes();
+        assert!(one >= mem::size_of::<u64>() * 2);
+        assert_eq!(counter(100).estimated_heap_bytes(), 100 * one);
+
+        // Counting keys does not grow the estimate beyond its bound.
+        let mut bounded = counter(2);
+        let estimate = bounded.estimated_heap_bytes();
+        for key in 0..10 {
+            bounded.add(key, Instant::now());
+        }
+        assert_eq!(bounded.estimated_heap_bytes(), estimate);
+    }
+
+    #[test]
+    fn evicts_first_counted_key() {
+        let start = Instant::now();
+        let mut counter =
+            WindowedCounter::new(Duration::from_secs(1), NonZeroUsize::new(2).unwrap());
+
+        for key in ["a", "b", "a", "c"] {
+            counter.add(key, start);
+        }
+        assert_eq!(counter.count(&"a"), 0);
+        assert_eq!(counter.count(&"b"), 1);
+        assert_eq!(counter.count(&"c"), 1);
+
+        // Counting an already counted key does not evict anything.
+        counter.add("b", start);
+        let closed = counter.add("a", start + Duration::from_secs(1)).unwrap();
+        assert_eq!(closed.counts, vec![("b", 2), ("c", 1)]);
+    }
+}
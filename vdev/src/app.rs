@@ -1,6 +1,9 @@
 use std::ffi::{OsStr, OsString};
 pub use std::process::Command;
-use std::{borrow::Cow, env, path::PathBuf, process::ExitStatus, time::Duration};
+use std::{
+    borrow::Cow, collections::HashSet, env, path::PathBuf, process::ExitStatus, sync::Mutex,
+    time::Duration,
+};
 
 use anyhow::{bail, Context as _, Result};
 use indicatif::{ProgressBar, ProgressStyle};
@@ -26,6 +29,7 @@ pub static SHELL: Lazy<OsString> =
 static VERBOSITY: OnceCell<LevelFilter> = OnceCell::new();
 static CONFIG: OnceCell<Config> = OnceCell::new();
 static PATH: OnceCell<String> = OnceCell::new();
+static MASKED_ENV_VARS: Lazy<Mutex<HashSet<String>>> = Lazy::new(Default::default);
 
 pub fn verbosity() -> &'static LevelFilter {
     VERBOSITY.get().expect("verbosity is not initialized")
@@ -39,6 +43,21 @@ pub fn path() -> &'static String {
     PATH.get().expect("path is not initialized")
 }
 
+/// Hide the value of the named environment variable whenever a command is logged.
+pub fn mask_env_var(key: impl Into<String>) {
+    MASKED_ENV_VARS.lock().unwrap().insert(key.into());
+}
+
+/// Whether the value of the named environment variable is hidden in the logged commands.
+pub fn is_masked(key: &str) -> bool {
+    MASKED_ENV_VARS.lock().unwrap().contains(key)
+}
+
+/// Hide the value of a `KEY=VALUE` or `--flag=KEY=VALUE` argument if `KEY` is masked.
+pub fn mask_arg(arg: &str) -> Cow<'_, str> {
+    mask_arg_with(arg, &MASKED_ENV_VARS.lock().unwrap())
+}
+
 pub fn set_repo_dir() -> Result<()> {
     env::set_current_dir(path()).context("Could not change directory")
 }
@@ -52,6 +71,7 @@ pub trait CommandExt {
     fn run(&mut self) -> Result<ExitStatus>;
     fn wait(&mut self, message: impl Into<Cow<'static, str>>) -> Result<()>;
     fn pre_exec(&self);
+    fn describe(&self) -> String;
 }
 
 impl CommandExt for Command {
@@ -92,7 +112,10 @@ impl CommandExt for Command {
             Ok(())
         } else {
             let exit = status.code().unwrap();
-            bail!("command: {self:?}\n  failed with exit code: {exit}")
+            bail!(
+                "command: {}\n  failed with exit code: {exit}",
+                self.describe()
+            )
         }
     }
 
@@ -124,18 +147,46 @@ impl CommandExt for Command {
 
     /// Print out a pre-execution debug message.
     fn pre_exec(&self) {
-        debug!("Executing: {self:?}");
+        debug!("Executing: {}", self.describe());
         if let Some(cwd) = self.get_current_dir() {
             debug!("  in working directory {cwd:?}");
         }
+    }
+
+    /// Describe the command for messages, without the values of masked environment variables.
+    ///
+    /// Unlike the `Debug` output, masked values are hidden both in the environment and in
+    /// `KEY=VALUE` arguments.
+    fn describe(&self) -> String {
+        let masked = MASKED_ENV_VARS.lock().unwrap();
+        let mut description = self.get_program().to_string_lossy().into_owned();
+        for arg in self.get_args() {
+            description.push(' ');
+            description.push_str(&mask_arg_with(&arg.to_string_lossy(), &masked));
+        }
         for (key, value) in self.get_envs() {
             let key = key.to_string_lossy();
-            if let Some(value) = value {
-                debug!("  ${key}={:?}", value.to_string_lossy());
+            if value.is_some() && masked.contains(key.as_ref()) {
+                description.push_str(&format!("\n  ${key}=<masked>"));
+            } else if let Some(value) = value {
+                description.push_str(&format!("\n  ${key}={:?}", value.to_string_lossy()));
             } else {
-                debug!("  unset ${key}");
+                description.push_str(&format!("\n  unset ${key}"));
             }
         }
+        description
+    }
+}
+
+/// Mask an argument against the given set of masked variables, see [`mask_arg`].
+fn mask_arg_with<'a>(arg: &'a str, masked: &HashSet<String>) -> Cow<'a, str> {
+    match arg.split_once('=') {
+        Some((flag, value)) if flag.starts_with('-') => match mask_arg_with(value, masked) {
+            Cow::Owned(value) => format!("{flag}={value}").into(),
+            Cow::Borrowed(_) => arg.into(),
+        },
+        Some((key, _)) if masked.contains(key) => format!("{key}=<masked>").into(),
+        _ => arg.into(),
     }
 }
 
@@ -180,3 +231,38 @@ pub fn set_global_config(config: Config) {
 pub fn set_global_path(path: String) {
     PATH.set(path).expect("could not set path");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn failed_command_error_masks_secrets() {
+        VERBOSITY.get_or_init(|| LevelFilter::Off);
+        mask_env_var("VDEV_TEST_SECRET");
+        let error = Command::new("/bin/sh")
+            .args(["-c", "exit 1", "VDEV_TEST_SECRET=hunter2"])
+            .env("VDEV_TEST_SECRET", "hunter2")
+            .env("VDEV_TEST_PLAIN", "visible")
+            .check_run()
+            .unwrap_err()
+            .to_string();
+
+        assert!(!error.contains("hunter2"), "{error}");
+        assert!(error.contains("VDEV_TEST_SECRET=<masked>"), "{error}");
+        assert!(error.contains("$VDEV_TEST_PLAIN=\"visible\""), "{error}");
+    }
+
+    #[test]
+    fn masks_flag_arguments() {
+        mask_env_var("VDEV_TEST_TOKEN");
+        assert_eq!(mask_arg("VDEV_TEST_TOKEN=abc"), "VDEV_TEST_TOKEN=<masked>");
+        assert_eq!(
+            mask_arg("--build-arg=VDEV_TEST_TOKEN=abc"),
+            "--build-arg=VDEV_TEST_TOKEN=<masked>"
+        );
+        assert_eq!(mask_arg("--env=OTHER=abc"), "--env=OTHER=abc");
+        assert_eq!(mask_arg("/src:/dst"), "/src:/dst");
+    }
+}
@@ -0,0 +1,60 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use clap::{Args, ValueEnum};
+use serde::Serialize;
+
+use crate::testing::{config::IntegrationTestConfig, state};
+
+/// List the integrations and their environments
+#[derive(Args, Debug)]
+#[command()]
+pub struct Cli {
+    /// The output format
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Format {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Serialize)]
+struct Environment {
+    name: String,
+    active: bool,
+}
+
+impl Cli {
+    pub fn exec(self) -> Result<()> {
+        let mut integrations = BTreeMap::new();
+        for (integration, config) in IntegrationTestConfig::collect_all()? {
+            let active_envs = state::EnvsDir::new(&integration).list_active()?;
+            let environments = config
+                .environments()
+                .keys()
+                .map(|name| Environment {
+                    name: name.clone(),
+                    active: active_envs.contains(name),
+                })
+                .collect::<Vec<_>>();
+            integrations.insert(integration, environments);
+        }
+
+        match self.format {
+            Format::Text => {
+                for (integration, environments) in integrations {
+                    println!("{integration}");
+                    for environment in environments {
+                        let active = if environment.active { " (active)" } else { "" };
+                        println!("  {}{active}", environment.name);
+                    }
+                }
+            }
+            Format::Json => println!("{}", serde_json::to_string_pretty(&integrations)?),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,29 @@
+use anyhow::Result;
+use clap::Args;
+
+use crate::testing::integration::IntegrationTest;
+
+/// Show the logs of an active environment
+#[derive(Args, Debug)]
+#[command()]
+pub struct Cli {
+    /// The desired integration
+    integration: String,
+
+    /// The desired environment
+    environment: String,
+
+    /// The service to show the logs of. If not present, the logs of all services are shown.
+    service: Option<String>,
+
+    /// Keep streaming new log output
+    #[arg(short, long)]
+    follow: bool,
+}
+
+impl Cli {
+    pub fn exec(self) -> Result<()> {
+        IntegrationTest::new(self.integration, self.environment)?
+            .logs(self.service.as_deref(), self.follow)
+    }
+}
@@ -3,8 +3,12 @@ crate::cli_subcommands! {
 
 These test setups are organized into a set of integrations, located in subdirectories
 `scripts/integration`.  For each integration, there is a matrix of environments, described in the
-`matrix` setting in the `test.yaml` file contained therein."#
+`matrix` setting in the `test.yaml` file contained therein. The file may instead be written as
+`test.yml`, `test.json`, or `test.toml`."#
 
+    mod list,
+    mod logs,
+    mod prepare,
     mod show,
     mod start,
     mod stop,
@@ -0,0 +1,31 @@
+use anyhow::Result;
+use clap::Args;
+
+use crate::testing::integration::IntegrationTest;
+use crate::util::parse_env_var;
+
+/// Start an environment and its test runner, without running the tests
+///
+/// This leaves the environment up, so that the tests can be run by hand in the runner container.
+/// An environment that is already up is left as is.
+#[derive(Args, Debug)]
+#[command()]
+pub struct Cli {
+    /// The desired integration
+    integration: String,
+
+    /// The desired environment
+    environment: String,
+
+    /// Set an environment variable for the compose services, overriding the configured value
+    #[arg(long = "env", value_name = "KEY=VALUE", value_parser = parse_env_var)]
+    env: Vec<(String, String)>,
+}
+
+impl Cli {
+    pub fn exec(self) -> Result<()> {
+        IntegrationTest::new(self.integration, self.environment)?
+            .with_env_overrides(self.env)
+            .prepare()
+    }
+}
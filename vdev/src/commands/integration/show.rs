@@ -40,7 +40,7 @@ impl Cli {
                 let envs_dir = state::EnvsDir::new(&integration);
                 let active_envs = envs_dir.list_active()?;
 
-                println!("Test args: {}", config.args.join(" "));
+                println!("Test args: {}", config.test_args().join(" "));
 
                 println!("Environments:");
                 for environment in config.environments().keys() {
@@ -2,6 +2,7 @@ use anyhow::Result;
 use clap::Args;
 
 use crate::testing::integration::IntegrationTest;
+use crate::util::parse_env_var;
 
 /// Start an environment
 #[derive(Args, Debug)]
@@ -12,10 +13,16 @@ pub struct Cli {
 
     /// The desired environment
     environment: String,
+
+    /// Set an environment variable for the compose services, overriding the configured value
+    #[arg(long = "env", value_name = "KEY=VALUE", value_parser = parse_env_var)]
+    env: Vec<(String, String)>,
 }
 
 impl Cli {
     pub fn exec(self) -> Result<()> {
-        IntegrationTest::new(self.integration, self.environment)?.start()
+        IntegrationTest::new(self.integration, self.environment)?
+            .with_env_overrides(self.env)
+            .start()
     }
 }
@@ -1,5 +1,6 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::Args;
+use glob::Pattern;
 
 use crate::testing::integration::{self, IntegrationTest, OldIntegrationTest};
 use crate::testing::state::EnvsDir;
@@ -11,35 +12,66 @@ pub struct Cli {
     /// The desired integration
     integration: String,
 
-    /// The desired environment. If not present, all running environments are stopped.
+    /// The desired environment. If not present, all running environments are stopped. Glob
+    /// patterns such as `3.*` stop all active environments that match.
     environment: Option<String>,
 
     /// Use the currently defined configuration if the environment is not up
     #[arg(short, long)]
     force: bool,
+
+    /// Verify that no containers of the environment remain after stopping it
+    #[arg(long)]
+    verify: bool,
 }
 
 impl Cli {
     pub fn exec(self) -> Result<()> {
         // Temporary hack to run old-style integration tests
         if self.environment.is_none() && integration::old_exists(&self.integration)? {
-            let integration = OldIntegrationTest::new(&self.integration);
+            let integration = OldIntegrationTest::new(&self.integration)?;
             return integration.stop();
         }
 
-        if let Some(environment) = self.environment {
-            IntegrationTest::new(self.integration, environment)?.stop(self.force)
+        let pattern = match self.environment {
+            Some(environment) if !is_glob(&environment) => {
+                return IntegrationTest::new(self.integration, environment)?
+                    .stop(self.force, self.verify);
+            }
+            Some(environment) => Some(
+                Pattern::new(&environment)
+                    .with_context(|| format!("invalid environment pattern {environment:?}"))?,
+            ),
+            None => None,
+        };
+
+        let mut envs = EnvsDir::new(&self.integration)
+            .list_active()?
+            .into_iter()
+            .filter(|environment| pattern.as_ref().map_or(true, |p| p.matches(environment)))
+            .collect::<Vec<_>>();
+        envs.sort();
+
+        if envs.is_empty() {
+            match pattern {
+                Some(pattern) => println!(
+                    "No environments for {:?} matching {:?} are active.",
+                    self.integration,
+                    pattern.as_str()
+                ),
+                None => println!("No environments for {:?} are active.", self.integration),
+            }
         } else {
-            let envs = EnvsDir::new(&self.integration).list_active()?;
-            if envs.is_empty() {
-                println!("No environments for {:?} are active.", self.integration);
-            } else {
-                for environment in envs {
-                    IntegrationTest::new(self.integration.clone(), environment)?
-                        .stop(self.force)?;
-                }
+            for environment in envs {
+                IntegrationTest::new(self.integration.clone(), environment.clone())?
+                    .stop(self.force, self.verify)?;
+                info!("Stopped environment {environment}");
             }
-            Ok(())
         }
+        Ok(())
     }
 }
+
+fn is_glob(environment: &str) -> bool {
+    environment.contains(['*', '?', '['])
+}
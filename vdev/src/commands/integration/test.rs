@@ -1,9 +1,18 @@
-use anyhow::Result;
+use std::collections::{BTreeMap, HashSet};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::num::NonZeroUsize;
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::Mutex;
+use std::{env, thread};
+
+use anyhow::{bail, Context as _, Result};
 use clap::Args;
+use log::LevelFilter;
 
+use crate::app::{self, CommandExt as _};
 use crate::testing::integration::{self, IntegrationTest, OldIntegrationTest};
-use crate::testing::runner::{ContainerTestRunner, IntegrationTestRunner};
-use crate::testing::{config::IntegrationTestConfig, state::EnvsDir};
+use crate::testing::{config::IntegrationTestConfig, results::TestSummary, state::EnvsDir};
+use crate::util::{parse_env_var, run_concurrently};
 
 /// Execute integration tests
 ///
@@ -13,6 +22,14 @@ use crate::testing::{config::IntegrationTestConfig, state::EnvsDir};
 /// If no environment is named, but some have been started already, only those environments are run.
 ///
 /// Otherwise, all environments are started, the test run, and then stopped.
+///
+/// Each environment has its own compose project, network, and runner container, so with
+/// `--parallel` several of them can be tested at once. Services that publish fixed host ports still
+/// conflict between environments. The runner image is built once, and then each environment is
+/// tested by a separate `vdev` process, whose output is shown along with its result once it is done.
+///
+/// The result of each environment is recorded, so that `--rerun-failed` can run only the
+/// environments that failed in their last run.
 #[derive(Args, Debug)]
 #[command()]
 pub struct Cli {
@@ -22,6 +39,31 @@ pub struct Cli {
     /// The desired environment (optional)
     environment: Option<String>,
 
+    /// Only run the environments whose last test run failed
+    #[arg(long, conflicts_with = "environment")]
+    rerun_failed: bool,
+
+    /// Only run the tests whose name contains this string
+    ///
+    /// This is passed to the test command as a filter expression, so it can't be combined with
+    /// `-E`/`--filter-expr` in the extra arguments, as nextest would run the tests matching either
+    /// of them. Add `and test(...)` to that filter expression instead.
+    #[arg(long)]
+    test: Option<String>,
+
+    /// The number of environments to test at once
+    #[arg(long, value_name = "N", default_value = "1", conflicts_with = "environment")]
+    parallel: NonZeroUsize,
+
+    /// Set an environment variable for the compose services and the tests, overriding the
+    /// configured value
+    #[arg(long = "env", value_name = "KEY=VALUE", value_parser = parse_env_var)]
+    env: Vec<(String, String)>,
+
+    /// Use the runner image that was already built for the environments tested in parallel
+    #[arg(long, hide = true, requires = "environment")]
+    prebuilt_runner: bool,
+
     /// Extra test command arguments
     args: Vec<String>,
 }
@@ -30,39 +72,220 @@ impl Cli {
     pub fn exec(self) -> Result<()> {
         // Temporary hack to run old-style integration tests
         if self.environment.is_none() && integration::old_exists(&self.integration)? {
-            let integration = OldIntegrationTest::new(&self.integration);
+            let integration = OldIntegrationTest::new(&self.integration)?;
             integration.build()?;
             return integration.test();
         }
 
-        let (_test_dir, config) = IntegrationTestConfig::load(&self.integration)?;
+        let (_, config) = IntegrationTestConfig::load(&self.integration)?;
         let envs = config.environments();
+        let mut args = config.test_args();
 
         let env_vars = config.env.unwrap_or_default();
+        args.extend(self.args.iter().cloned());
+        if let Some(test) = &self.test {
+            // Filter expressions are intersected with the name filters from the test config, but
+            // several filter expressions are combined into a union.
+            if args.iter().any(|arg| is_filter_expr_arg(arg)) {
+                bail!(
+                    "`--test` can't be combined with a `-E` filter expression, \
+                    add `and test({test})` to the filter expression instead"
+                );
+            }
+            args.extend(["-E".to_owned(), format!("test({test})")]);
+        }
 
-        let mut args = config.args;
-        args.extend(self.args);
-
+        let envs_dir = EnvsDir::new(&self.integration);
         if let Some(environment) = &self.environment {
-            IntegrationTest::new(&self.integration, environment)?.test(&env_vars, &args)
+            let mut test = IntegrationTest::new(&self.integration, environment)?
+                .with_env_overrides(self.env.clone());
+            if self.prebuilt_runner {
+                test = test.with_prebuilt_runner();
+            }
+            let summary = test.test(&env_vars, &args);
+            envs_dir.save_result(
+                environment,
+                matches!(&summary, Ok(summary) if summary.success),
+            )?;
+            let summary = summary?;
+            info!("Tests in environment {environment}: {summary}");
+            summary.check()
         } else {
-            let runner = IntegrationTestRunner::new(self.integration.clone())?;
-            runner.ensure_network()?;
-
-            let active_envs = EnvsDir::new(&self.integration).list_active()?;
-            for env_name in envs.keys() {
-                if !(active_envs.is_empty() || active_envs.contains(env_name)) {
-                    continue;
+            let failed_envs = if self.rerun_failed {
+                let results = envs_dir.read_results()?;
+                let failed_envs = envs
+                    .keys()
+                    .filter(|env_name| results.get(*env_name) == Some(&false))
+                    .cloned()
+                    .collect::<HashSet<_>>();
+                if failed_envs.is_empty() {
+                    info!("No environments failed in their last run");
+                    return Ok(());
                 }
+                Some(failed_envs)
+            } else {
+                None
+            };
 
-                IntegrationTest::new(&self.integration, env_name)?.test(&env_vars, &args)?;
-            }
+            let active_envs = envs_dir.list_active()?;
+            let env_names = envs
+                .keys()
+                .filter(|env_name| active_envs.is_empty() || active_envs.contains(*env_name))
+                .filter(|env_name| {
+                    failed_envs
+                        .as_ref()
+                        .map_or(true, |failed_envs| failed_envs.contains(*env_name))
+                })
+                .cloned()
+                .collect::<Vec<_>>();
+
+            let results = if self.parallel.get() > 1 && env_names.len() > 1 {
+                self.test_in_parallel(&env_names)?
+            } else {
+                env_names
+                    .iter()
+                    .map(|env_name| {
+                        let result = self.test_environment(env_name, &env_vars, &args);
+                        match &result {
+                            Ok(summary) => {
+                                info!("Tests passed in environment {env_name}: {summary}");
+                            }
+                            Err(error) => error!("Tests failed in environment {env_name}: {error}"),
+                        }
+                        result.map(|_| ())
+                    })
+                    .collect()
+            };
 
-            if active_envs.is_empty() {
-                runner.stop()?;
+            let mut failures = Vec::new();
+            for (env_name, result) in env_names.iter().zip(results) {
+                envs_dir.save_result(env_name, result.is_ok())?;
+                if result.is_err() {
+                    failures.push(env_name.clone());
+                }
             }
+            info!(
+                "Tests passed in {} of {} environments",
+                env_names.len() - failures.len(),
+                env_names.len()
+            );
 
+            if !failures.is_empty() {
+                bail!("tests failed in environments: {}", failures.join(", "));
+            }
             Ok(())
         }
     }
+
+    fn test_environment(
+        &self,
+        env_name: &str,
+        env_vars: &BTreeMap<String, String>,
+        args: &[String],
+    ) -> Result<TestSummary> {
+        let summary = IntegrationTest::new(&self.integration, env_name)?
+            .with_env_overrides(self.env.clone())
+            .test(env_vars, args)?;
+        summary.check().map(|()| summary)
+    }
+
+    /// Test each environment in a `vdev` subprocess, with at most `--parallel` of them at once. The
+    /// output of each subprocess is buffered, and printed along with its result once it is done, so
+    /// that the output of the environments is not interleaved.
+    fn test_in_parallel(&self, env_names: &[String]) -> Result<Vec<Result<()>>> {
+        // The environments share the runner image, so it is built once rather than by each of them
+        // at the same time.
+        IntegrationTest::new(&self.integration, &env_names[0])?.build_runner()?;
+
+        for (key, _) in &self.env {
+            app::mask_env_var(key.clone());
+        }
+        let vdev = env::current_exe().context("could not find the vdev executable")?;
+        let print_lock = Mutex::new(());
+        Ok(run_concurrently(env_names, self.parallel.get(), |env_name| {
+            let mut command = Command::new(&vdev);
+            command.in_repo();
+            command.args(verbosity_args());
+            command.args(["integration", "test", "--prebuilt-runner"]);
+            command.args([&self.integration, env_name]);
+            if let Some(test) = &self.test {
+                command.args(["--test", test]);
+            }
+            for (key, value) in &self.env {
+                command.arg(format!("--env={key}={value}"));
+            }
+            command.arg("--").args(&self.args);
+
+            let result = capture_combined_output(&mut command);
+            let _lock = print_lock.lock().expect("print lock is poisoned");
+            let result = result.and_then(|(status, output)| {
+                io::stdout().write_all(&output)?;
+                if status.success() {
+                    Ok(())
+                } else {
+                    bail!("vdev exited with {status}")
+                }
+            });
+            match &result {
+                Ok(()) => info!("Tests passed in environment {env_name}"),
+                Err(error) => error!("Tests failed in environment {env_name}: {error}"),
+            }
+            result
+        }))
+    }
+}
+
+/// The flags that give a `vdev` subprocess the same verbosity as this process.
+fn verbosity_args() -> &'static [&'static str] {
+    match *app::verbosity() {
+        LevelFilter::Off => &["-qqq"],
+        LevelFilter::Error => &["-qq"],
+        LevelFilter::Warn => &["-q"],
+        LevelFilter::Info => &[],
+        LevelFilter::Debug => &["-v"],
+        LevelFilter::Trace => &["-vv"],
+    }
+}
+
+/// Run the command, capturing its standard output and error together, in the order their lines
+/// are written.
+fn capture_combined_output(command: &mut Command) -> Result<(ExitStatus, Vec<u8>)> {
+    command.pre_exec();
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("failed to run {}", command.describe()))?;
+    let stdout = child.stdout.take().expect("stdout is piped");
+    let stderr = child.stderr.take().expect("stderr is piped");
+
+    let output = Mutex::new(Vec::new());
+    thread::scope(|scope| {
+        let stderr = scope.spawn(|| copy_lines(stderr, &output));
+        copy_lines(stdout, &output)?;
+        stderr.join().expect("output thread panicked")
+    })?;
+
+    let status = child.wait()?;
+    Ok((status, output.into_inner().expect("output lock is poisoned")))
+}
+
+fn copy_lines(input: impl Read, output: &Mutex<Vec<u8>>) -> io::Result<()> {
+    for line in BufReader::new(input).split(b'\n') {
+        let mut line = line?;
+        line.push(b'\n');
+        output
+            .lock()
+            .expect("output lock is poisoned")
+            .extend(line);
+    }
+    Ok(())
+}
+
+fn is_filter_expr_arg(arg: &str) -> bool {
+    arg.starts_with("-E")
+        || arg == "--filter-expr"
+        || arg.starts_with("--filter-expr=")
+        || arg == "--filterset"
+        || arg.starts_with("--filterset=")
 }
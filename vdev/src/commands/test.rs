@@ -47,6 +47,8 @@ impl Cli {
             }
         }
 
-        runner.test(&parse_env(self.env.unwrap_or_default()), &args)
+        let summary = runner.test(&parse_env(self.env.unwrap_or_default()), &args)?;
+        info!("Tests: {summary}");
+        summary.check()
     }
 }
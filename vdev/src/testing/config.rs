@@ -1,4 +1,5 @@
 use std::collections::{BTreeMap, HashMap};
+use std::ffi::OsStr;
 use std::fs;
 use std::path::{Path, PathBuf};
 
@@ -9,7 +10,9 @@ use serde::Deserialize;
 
 use crate::{app, util};
 
-const FILE_NAME: &str = "test.yaml";
+/// The names of the integration test configuration file, one per supported format.
+const FILE_NAMES: [&str; 4] = ["test.yaml", "test.yml", "test.json", "test.toml"];
+const FEATURES_VAR: &str = "FEATURES";
 
 #[derive(Deserialize, Debug)]
 pub struct RustToolchainRootConfig {
@@ -34,27 +37,105 @@ impl RustToolchainConfig {
     }
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Clone, Debug, PartialEq)]
 pub struct IntegrationTestConfig {
     pub args: Vec<String>,
+    /// Extra build arguments for the runner image, passed as `--build-arg` arguments. They are also
+    /// exported to the compose commands, so that images built by the compose file can use them.
+    pub build_args: Option<BTreeMap<String, String>>,
     pub env: Option<BTreeMap<String, String>>,
+    /// The cargo features to build the tests with. They are passed to the tests as a `--features`
+    /// argument, and to the image builds as the comma-separated `FEATURES` build argument.
+    pub features: Option<Vec<String>>,
+    /// HTTP endpoints that must respond as expected before an environment is considered started.
+    /// They are polled once the compose services are up, and the environment is stopped again if
+    /// one of them fails.
+    pub http_checks: Option<Vec<HttpCheck>>,
+    /// Extra host paths to mount into the runner container, mapped to their container paths. Host
+    /// paths are relative to the test directory.
+    pub mounts: Option<BTreeMap<String, String>>,
+    /// The number of replicas to start for each compose service, passed as `--scale` arguments.
+    pub scale: Option<BTreeMap<String, u32>>,
+    /// A file of `KEY=value` lines, relative to the test directory, with credentials to pass to the
+    /// compose services and the test runner. The file should be ignored by git, and its values are
+    /// masked in the logged commands.
+    pub secrets: Option<String>,
+    /// A shell command run in the test directory before an environment is started, to prepare
+    /// anything the compose services need.
+    pub setup: Option<String>,
+    /// A shell command run in the test directory after an environment is stopped, to clean up
+    /// anything `down` does not remove.
+    pub teardown: Option<String>,
     matrix: Vec<LinkedHashMap<String, Vec<String>>>,
 }
 
+/// A readiness check of an HTTP endpoint of an environment's services.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct HttpCheck {
+    /// The URL to request, from a container attached to the environment's network, so that the
+    /// compose services can be addressed by name.
+    pub url: String,
+    /// The expected status code of the response.
+    #[serde(default = "default_http_check_status")]
+    pub status: u16,
+    /// A substring that the body of the response must contain.
+    pub body_contains: Option<String>,
+    /// Whether to follow redirects, checking the response they lead to.
+    #[serde(default)]
+    pub follow_redirects: bool,
+    /// How long to poll the endpoint for before giving up, in seconds.
+    #[serde(default = "default_http_check_timeout")]
+    pub timeout_secs: u64,
+}
+
+const fn default_http_check_status() -> u16 {
+    200
+}
+
+const fn default_http_check_timeout() -> u64 {
+    60
+}
+
 pub type Environment = HashMap<String, String>;
 
 impl IntegrationTestConfig {
+    /// Parse a configuration file in the format given by its extension: JSON, TOML, or else YAML.
     fn parse_file(config_file: &Path) -> Result<Self> {
         let contents = fs::read_to_string(config_file)
             .with_context(|| format!("failed to read {}", config_file.display()))?;
-        let config: IntegrationTestConfig = serde_yaml::from_str(&contents).with_context(|| {
+        let config: Result<IntegrationTestConfig> =
+            match config_file.extension().and_then(OsStr::to_str) {
+                Some("json") => serde_json::from_str(&contents).map_err(Into::into),
+                Some("toml") => toml::from_str(&contents).map_err(Into::into),
+                _ => serde_yaml::from_str(&contents).map_err(Into::into),
+            };
+
+        config.with_context(|| {
             format!(
                 "failed to parse integration test configuration file {}",
                 config_file.display()
             )
-        })?;
+        })
+    }
 
-        Ok(config)
+    /// Find the configuration file in the test directory, whichever its format.
+    fn find_file(test_dir: &Path) -> Result<Option<PathBuf>> {
+        let mut found = Vec::new();
+        for file_name in FILE_NAMES {
+            let config_file = test_dir.join(file_name);
+            if util::exists(&config_file)? {
+                found.push(config_file);
+            }
+        }
+
+        if found.len() > 1 {
+            bail!(
+                "found multiple integration test configuration files in {}: {}",
+                test_dir.display(),
+                found.iter().map(|path| path.display()).join(", ")
+            );
+        }
+        Ok(found.pop())
     }
 
     pub fn environments(&self) -> LinkedHashMap<String, Environment> {
@@ -74,6 +155,89 @@ impl IntegrationTestConfig {
         environments
     }
 
+    /// The arguments for the test command, including the configured `features`.
+    pub fn test_args(&self) -> Vec<String> {
+        let mut args = self.args.clone();
+        if let Some(features) = self.joined_features() {
+            args.extend(["--features".to_owned(), features]);
+        }
+        args
+    }
+
+    /// The variables for the image builds: the configured `build_args`, and `FEATURES` if any
+    /// `features` are configured.
+    pub fn build_env(&self) -> BTreeMap<String, String> {
+        let mut env = self.build_args.clone().unwrap_or_default();
+        if let Some(features) = self.joined_features() {
+            env.insert(FEATURES_VAR.to_owned(), features);
+        }
+        env
+    }
+
+    /// Resolve the `build_env` into `--build-arg` values for the runner image.
+    pub fn runner_build_args(&self) -> Vec<String> {
+        self.build_env()
+            .into_iter()
+            .map(|(key, value)| format!("{key}={value}"))
+            .collect()
+    }
+
+    fn joined_features(&self) -> Option<String> {
+        self.features
+            .as_ref()
+            .filter(|features| !features.is_empty())
+            .map(|features| features.join(","))
+    }
+
+    /// Resolve the configured `mounts` into `--volume` arguments for the runner container.
+    pub fn runner_volumes(&self, test_dir: &Path) -> Result<Vec<String>> {
+        let Some(mounts) = &self.mounts else {
+            return Ok(Vec::new());
+        };
+
+        mounts
+            .iter()
+            .map(|(host_path, container_path)| {
+                let host_path = dunce::canonicalize(test_dir.join(host_path))
+                    .with_context(|| format!("failed to canonicalize mount path {host_path:?}"))?;
+                Ok(format!("{}:{container_path}", host_path.display()))
+            })
+            .collect()
+    }
+
+    /// Read the variables from the configured `secrets` file. A missing file is not an error, so
+    /// that environments which don't strictly need the credentials can still be run.
+    pub fn secrets(&self, test_dir: &Path) -> Result<Environment> {
+        let Some(secrets) = &self.secrets else {
+            return Ok(Environment::new());
+        };
+
+        let path = test_dir.join(secrets);
+        if !util::exists(&path)? {
+            warn!("Secrets file {} does not exist", path.display());
+            return Ok(Environment::new());
+        }
+
+        let contents = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        contents
+            .lines()
+            .enumerate()
+            .map(|(index, line)| (index, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+            .map(|(index, line)| match line.split_once('=') {
+                Some((key, value)) if !key.trim().is_empty() => {
+                    Ok((key.trim().to_owned(), value.trim().to_owned()))
+                }
+                _ => bail!(
+                    "invalid line {} in secrets file {}, expected `KEY=value`",
+                    index + 1,
+                    path.display()
+                ),
+            })
+            .collect()
+    }
+
     pub fn load(integration: &str) -> Result<(PathBuf, Self)> {
         let test_dir: PathBuf = [app::path(), "scripts", "integration", integration]
             .iter()
@@ -82,19 +246,35 @@ impl IntegrationTestConfig {
             bail!("unknown integration: {}", integration);
         }
 
-        let config = Self::parse_file(&test_dir.join(FILE_NAME))?;
+        let Some(config_file) = Self::find_file(&test_dir)? else {
+            bail!(
+                "no integration test configuration file in {}, expected one of {}",
+                test_dir.display(),
+                FILE_NAMES.join(", ")
+            );
+        };
+
+        let config = Self::parse_file(&config_file)?;
         Ok((test_dir, config))
     }
 
+    /// Load the configuration of an integration, if it has one.
+    pub fn load_if_exists(integration: &str) -> Result<Option<Self>> {
+        let test_dir: PathBuf = [app::path(), "scripts", "integration", integration]
+            .iter()
+            .collect();
+        Self::find_file(&test_dir)?
+            .map(|config_file| Self::parse_file(&config_file))
+            .transpose()
+    }
+
     pub fn collect_all() -> Result<BTreeMap<String, Self>> {
         let mut configs = BTreeMap::new();
         let tests_dir: PathBuf = [app::path(), "scripts", "integration"].iter().collect();
         for entry in tests_dir.read_dir()? {
             let entry = entry?;
             if entry.path().is_dir() {
-                let config_file: PathBuf =
-                    [entry.path().to_str().unwrap(), FILE_NAME].iter().collect();
-                if util::exists(&config_file)? {
+                if let Some(config_file) = Self::find_file(&entry.path())? {
                     let config = Self::parse_file(&config_file)?;
                     configs.insert(entry.file_name().into_string().unwrap(), config);
                 }
@@ -104,3 +284,117 @@ impl IntegrationTestConfig {
         Ok(configs)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    const YAML: &str = r#"
+args:
+  - --lib
+  - sinks::http
+env:
+  HTTP_ADDRESS: http://server:8080
+features:
+  - http-integration-tests
+http_checks:
+  - url: http://server:8080/health
+    timeout_secs: 30
+matrix:
+  - version: ["1.0", "2.0"]
+"#;
+
+    const JSON: &str = r#"{
+  "args": ["--lib", "sinks::http"],
+  "env": { "HTTP_ADDRESS": "http://server:8080" },
+  "features": ["http-integration-tests"],
+  "http_checks": [{ "url": "http://server:8080/health", "timeout_secs": 30 }],
+  "matrix": [{ "version": ["1.0", "2.0"] }]
+}"#;
+
+    const TOML: &str = r#"
+args = ["--lib", "sinks::http"]
+features = ["http-integration-tests"]
+
+[env]
+HTTP_ADDRESS = "http://server:8080"
+
+[[http_checks]]
+url = "http://server:8080/health"
+timeout_secs = 30
+
+[[matrix]]
+version = ["1.0", "2.0"]
+"#;
+
+    /// Create an empty directory for the test, named after it.
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("vdev-config-{}-{name}", std::process::id()));
+        if dir.exists() {
+            fs::remove_dir_all(&dir).unwrap();
+        }
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn loads_equivalent_configs_in_each_format() {
+        let dir = test_dir("formats");
+        let mut configs = Vec::new();
+        for (file_name, contents) in [
+            ("test.yaml", YAML),
+            ("test.yml", YAML),
+            ("test.json", JSON),
+            ("test.toml", TOML),
+        ] {
+            let format_dir = dir.join(file_name);
+            fs::create_dir(&format_dir).unwrap();
+            fs::write(format_dir.join(file_name), contents).unwrap();
+
+            let config_file = IntegrationTestConfig::find_file(&format_dir).unwrap();
+            assert_eq!(config_file, Some(format_dir.join(file_name)));
+            configs.push(IntegrationTestConfig::parse_file(&config_file.unwrap()).unwrap());
+        }
+
+        let config = &configs[0];
+        assert!(configs.iter().all(|other| other == config));
+        assert_eq!(config.test_args().len(), 4);
+        assert_eq!(
+            config.environments().keys().collect::<Vec<_>>(),
+            ["1.0", "2.0"]
+        );
+        let checks = config.http_checks.as_ref().unwrap();
+        assert_eq!(checks[0].status, 200);
+        assert_eq!(checks[0].timeout_secs, 30);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_multiple_config_files() {
+        let dir = test_dir("multiple");
+        assert_eq!(IntegrationTestConfig::find_file(&dir).unwrap(), None);
+
+        fs::write(dir.join("test.yaml"), YAML).unwrap();
+        fs::write(dir.join("test.toml"), TOML).unwrap();
+        let error = IntegrationTestConfig::find_file(&dir).unwrap_err();
+        assert!(error.to_string().contains("multiple"), "{error}");
+        assert!(error.to_string().contains("test.toml"), "{error}");
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn reports_the_file_that_fails_to_parse() {
+        let dir = test_dir("invalid");
+        let config_file = dir.join("test.json");
+        fs::write(&config_file, YAML).unwrap();
+
+        let error = IntegrationTestConfig::parse_file(&config_file).unwrap_err();
+        assert!(error.to_string().contains("test.json"), "{error}");
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,16 +1,28 @@
-use std::{collections::BTreeMap, path::Path, path::PathBuf, process::Command};
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    process::Command,
+    thread,
+    time::{Duration, Instant},
+};
 
 use anyhow::{bail, Context, Result};
 
-use super::config::{Environment, IntegrationTestConfig, RustToolchainConfig};
+use super::config::{Environment, HttpCheck, IntegrationTestConfig, RustToolchainConfig};
+use super::results::TestSummary;
 use super::runner::{
-    ContainerTestRunner as _, IntegrationTestRunner, TestRunner as _, CONTAINER_TOOL,
-    NETWORK_ENV_VAR,
+    environment_id, ContainerTestRunner as _, IntegrationTestRunner, RunnerState, TestRunner as _,
+    CONTAINER_TOOL, NETWORK_ENV_VAR,
 };
 use super::state::EnvsDir;
 use crate::app::{self, CommandExt as _};
 use crate::util::exists;
 
+/// The image used to request the endpoints of the `http_checks`.
+const HTTP_CHECK_IMAGE: &str = "docker.io/curlimages/curl:7.87.0";
+/// The time between two requests of an endpoint that is not ready yet.
+const HTTP_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
 #[allow(clippy::dbg_macro)]
 fn old_integration_path(integration: &str) -> PathBuf {
     let filename = format!("docker-compose.{integration}.yml");
@@ -27,12 +39,19 @@ pub fn old_exists(integration: &str) -> Result<bool> {
 /// Temporary runner setup for old-style integration tests
 pub struct OldIntegrationTest {
     compose_path: PathBuf,
+    build_env: BTreeMap<String, String>,
 }
 
 impl OldIntegrationTest {
-    pub fn new(integration: &str) -> Self {
+    pub fn new(integration: &str) -> Result<Self> {
         let compose_path = old_integration_path(integration);
-        Self { compose_path }
+        let build_env = IntegrationTestConfig::load_if_exists(integration)?
+            .map(|config| config.build_env())
+            .unwrap_or_default();
+        Ok(Self {
+            compose_path,
+            build_env,
+        })
     }
 
     pub fn build(&self) -> Result<()> {
@@ -60,6 +79,7 @@ impl OldIntegrationTest {
             .expect("Could not parse `rust-toolchain.toml`")
             .channel;
         command.env("RUST_VERSION", rust_version);
+        command.envs(&self.build_env);
 
         command.check_run()
     }
@@ -69,7 +89,11 @@ pub struct IntegrationTest {
     integration: String,
     environment: String,
     test_dir: PathBuf,
+    /// The canonicalized path of the compose file, resolved once as `test_dir` does not change.
+    compose_file: String,
     config: IntegrationTestConfig,
+    secrets: Environment,
+    env_overrides: Environment,
     envs_dir: EnvsDir,
     runner: IntegrationTestRunner,
 }
@@ -79,32 +103,121 @@ impl IntegrationTest {
         let integration = integration.into();
         let environment = environment.into();
         let (test_dir, config) = IntegrationTestConfig::load(&integration)?;
+        let compose_path = test_dir.join("compose.yaml");
+        let compose_file = dunce::canonicalize(&compose_path)
+            .with_context(|| {
+                format!(
+                    "Could not canonicalize docker compose path {}",
+                    compose_path.display()
+                )
+            })?
+            .display()
+            .to_string();
+        let secrets = config.secrets(&test_dir)?;
+        for key in secrets.keys() {
+            app::mask_env_var(key.clone());
+        }
         let envs_dir = EnvsDir::new(&integration);
-        let runner = IntegrationTestRunner::new(integration.clone())?;
+        let runner = IntegrationTestRunner::new(
+            integration.clone(),
+            environment.clone(),
+            config.runner_volumes(&test_dir)?,
+            config.runner_build_args(),
+        )?;
 
         Ok(Self {
             integration,
             environment,
             test_dir,
+            compose_file,
             config,
+            secrets,
+            env_overrides: Environment::new(),
             envs_dir,
             runner,
         })
     }
 
+    /// Set environment variables for the compose services and the tests, taking precedence over
+    /// all configured values. Like the secrets, their values are masked in the logged commands.
+    pub fn with_env_overrides(
+        mut self,
+        overrides: impl IntoIterator<Item = (String, String)>,
+    ) -> Self {
+        for (key, value) in overrides {
+            app::mask_env_var(key.clone());
+            self.env_overrides.insert(key, value);
+        }
+        self
+    }
+
+    /// Use the runner image that was already built, see [`IntegrationTest::build_runner`].
+    pub fn with_prebuilt_runner(mut self) -> Self {
+        self.runner.set_prebuilt();
+        self
+    }
+
+    /// Build the runner image, which is shared by all the environments of the integration.
+    pub fn build_runner(&self) -> Result<()> {
+        self.runner.build()
+    }
+
     pub fn env_exists(&self) -> bool {
         self.envs_dir.exists(&self.environment)
     }
 
-    pub fn test(&self, env_vars: &BTreeMap<String, String>, args: &[String]) -> Result<()> {
+    pub fn test(
+        &self,
+        env_vars: &BTreeMap<String, String>,
+        args: &[String],
+    ) -> Result<TestSummary> {
         let active = self.env_exists();
         if !active {
             self.start()?;
         }
 
-        self.runner.test(env_vars, args)?;
+        let mut env_vars = env_vars.clone();
+        env_vars.extend(self.secrets.clone());
+        env_vars.extend(self.env_overrides.clone());
+        let summary = self.runner.test(&env_vars, args)?;
         if !active {
-            self.stop(false)?;
+            self.stop(false, false)?;
+        }
+        Ok(summary)
+    }
+
+    /// Start the environment, if it is not up yet, and the runner container, without running the
+    /// tests, so that they can be run by hand in the container.
+    pub fn prepare(&self) -> Result<()> {
+        if !self.env_exists() {
+            self.start()?;
+        }
+        self.runner.verify_state()?;
+
+        // The secrets and overrides are passed by name, so that their values are not printed.
+        let mut env_vars = self.config.env.clone().unwrap_or_default();
+        env_vars.retain(|key, _| !app::is_masked(key));
+        let mut inherited = self
+            .secrets
+            .keys()
+            .chain(self.env_overrides.keys())
+            .collect::<Vec<_>>();
+        inherited.sort();
+        inherited.dedup();
+        success!("Environment {} is ready", self.environment);
+        info!(
+            "Open a shell in the runner container with:\n  {}",
+            self.runner.shell_command(&env_vars, &inherited)
+        );
+        if !inherited.is_empty() {
+            info!(
+                "These variables are read from the calling shell: {}",
+                inherited
+                    .iter()
+                    .map(|key| key.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
         }
         Ok(())
     }
@@ -122,12 +235,38 @@ impl IntegrationTest {
             bail!("environment is already up");
         }
 
-        self.run_compose("Starting", &["up", "--detach"], cmd_config)?;
+        let scale_args = self
+            .config
+            .scale
+            .iter()
+            .flatten()
+            .map(|(service, replicas)| format!("{service}={replicas}"))
+            .collect::<Vec<_>>();
+        let mut args = vec!["up", "--detach"];
+        for scale in &scale_args {
+            args.extend(["--scale", scale]);
+        }
 
-        self.envs_dir.save(&self.environment, cmd_config)
+        if let Some(setup) = &self.config.setup {
+            self.run_hook("setup", setup, cmd_config)?;
+        }
+        self.run_compose("Starting", &args, cmd_config)?;
+
+        self.envs_dir.save(&self.environment, cmd_config)?;
+        if let Err(error) = self.wait_until_ready(cmd_config) {
+            // An environment that never became ready is not left up, as nothing would stop it.
+            if let Err(stop_error) = self.stop(false, false) {
+                warn!(
+                    "Could not stop environment {}: {stop_error}",
+                    self.environment
+                );
+            }
+            return Err(error);
+        }
+        Ok(())
     }
 
-    pub fn stop(&self, force: bool) -> Result<()> {
+    pub fn stop(&self, force: bool, verify: bool) -> Result<()> {
         let cmd_config: Environment = if self.envs_dir.exists(&self.environment) {
             self.envs_dir.read_config(&self.environment)?
         } else if force {
@@ -142,34 +281,224 @@ impl IntegrationTest {
         };
 
         self.run_compose("Stopping", &["down", "--timeout", "0"], &cmd_config)?;
+        self.stop_legacy_project(&cmd_config)?;
+        if verify {
+            self.verify_stopped(&cmd_config)?;
+        }
+
+        if let Some(teardown) = &self.config.teardown {
+            if let Err(error) = self.run_hook("teardown", teardown, &cmd_config) {
+                warn!(
+                    "Teardown of environment {} failed: {error}",
+                    self.environment
+                );
+            }
+        }
 
         self.envs_dir.remove(&self.environment)?;
-        if self.envs_dir.list_active()?.is_empty() {
+        if !matches!(self.runner.state()?, RunnerState::Missing) {
             self.runner.stop()?;
+            // The container is attached to the network, so it goes along with it.
+            self.runner.remove()?;
         }
+        self.runner.remove_network()?;
 
         Ok(())
     }
 
-    fn run_compose(&self, action: &str, args: &[&'static str], config: &Environment) -> Result<()> {
-        let compose_path: PathBuf = [&self.test_dir, Path::new("compose.yaml")].iter().collect();
-        let compose_file = dunce::canonicalize(compose_path)
-            .context("Could not canonicalize docker compose path")?
-            .display()
-            .to_string();
+    /// Show the logs of the environment's containers, or only those of `service`.
+    pub fn logs(&self, service: Option<&str>, follow: bool) -> Result<()> {
+        if !self.envs_dir.exists(&self.environment) {
+            bail!("environment is not up");
+        }
+        let cmd_config = self.envs_dir.read_config(&self.environment)?;
+
+        let mut args = vec!["logs"];
+        if follow {
+            args.push("--follow");
+        }
+        args.extend(service);
+
+        self.compose_command(&args, &cmd_config).check_run()
+    }
+
+    /// Poll the configured `http_checks` until each of them passes. If one of them times out, the
+    /// logs of the environment are shown to help tell why.
+    fn wait_until_ready(&self, config: &Environment) -> Result<()> {
+        for check in self.config.http_checks.iter().flatten() {
+            waiting!(
+                "Waiting for {} in environment {}",
+                check.url,
+                self.environment
+            );
+            if let Err(error) = self.poll_http_check(check) {
+                if let Err(logs_error) = self.compose_command(&["logs"], config).check_run() {
+                    warn!("Could not show the logs of the environment: {logs_error}");
+                }
+                return Err(error);
+            }
+        }
+        Ok(())
+    }
+
+    fn poll_http_check(&self, check: &HttpCheck) -> Result<()> {
+        let deadline = Instant::now() + Duration::from_secs(check.timeout_secs);
+        loop {
+            let reason = match self.request_http_check(check) {
+                Ok(()) => return Ok(()),
+                Err(reason) => reason,
+            };
+            if Instant::now() >= deadline {
+                bail!(
+                    "{} was not ready after {} seconds: {reason}",
+                    check.url,
+                    check.timeout_secs
+                );
+            }
+            thread::sleep(HTTP_CHECK_INTERVAL);
+        }
+    }
+
+    /// Request the endpoint of the check once, returning why the response is not the expected one.
+    fn request_http_check(&self, check: &HttpCheck) -> Result<(), String> {
+        let mut command = Command::new(&*CONTAINER_TOOL);
+        command.args(["run", "--rm", "--network", &self.runner.network_name()]);
+        command.args([HTTP_CHECK_IMAGE, "--silent", "--max-time", "5"]);
+        command.args(["--write-out", "\n%{http_code}"]);
+        if check.follow_redirects {
+            command.arg("--location");
+        }
+        command.arg(&check.url);
+
+        let output = command
+            .capture_output()
+            .map_err(|error| error.to_string())?;
+        let (body, status) = output.rsplit_once('\n').unwrap_or(("", &output));
+        match status.trim().parse::<u16>() {
+            Ok(0) | Err(_) => Err("no response".to_owned()),
+            Ok(status) if status != check.status => {
+                Err(format!("expected status {}, got {status}", check.status))
+            }
+            Ok(_) => match &check.body_contains {
+                Some(expected) if !body.contains(expected.as_str()) => {
+                    Err(format!("response body does not contain {expected:?}"))
+                }
+                _ => Ok(()),
+            },
+        }
+    }
+
+    /// Check that no containers of the environment remain after it was stopped, retrying the
+    /// shutdown once if any do.
+    fn verify_stopped(&self, config: &Environment) -> Result<()> {
+        if self.running_containers(config)?.is_empty() {
+            return Ok(());
+        }
+
+        warn!(
+            "Containers remain in environment {}, retrying shutdown",
+            self.environment
+        );
+        self.run_compose("Stopping", &["down", "--timeout", "0"], config)?;
+
+        let remaining = self.running_containers(config)?;
+        if !remaining.is_empty() {
+            bail!(
+                "containers remain after stopping environment {}: {}",
+                self.environment,
+                remaining.join(", ")
+            );
+        }
+        Ok(())
+    }
+
+    /// Take down the containers of an environment started by an earlier version of vdev, which used
+    /// the default compose project named after the test directory. At most one environment of the
+    /// integration could be up in that project, so it is taken down whichever environment is
+    /// stopped.
+    fn stop_legacy_project(&self, config: &Environment) -> Result<()> {
+        let containers = self
+            .legacy_compose_command(&["ps", "--quiet"], config)
+            .capture_output()?;
+        if containers.trim().is_empty() {
+            return Ok(());
+        }
+
+        warn!(
+            "Stopping the containers of integration {} started by an earlier version of vdev",
+            self.integration
+        );
+        self.legacy_compose_command(&["down", "--timeout", "0"], config)
+            .check_run()
+    }
 
+    fn running_containers(&self, config: &Environment) -> Result<Vec<String>> {
+        let output = self
+            .compose_command(&["ps", "--quiet"], config)
+            .capture_output()?;
+        Ok(output
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(str::to_owned)
+            .collect())
+    }
+
+    fn run_compose(&self, action: &str, args: &[&str], config: &Environment) -> Result<()> {
+        let mut command = self.compose_command(args, config);
+
+        waiting!("{action} environment {}", self.environment);
+        command.check_run()
+    }
+
+    /// Run one of the shell commands configured for the environment, with the same working
+    /// directory and variables as the compose commands.
+    fn run_hook(&self, name: &str, script: &str, config: &Environment) -> Result<()> {
+        let mut command = Command::new(&*app::SHELL);
+        command.args(["-c", script]);
+        self.apply_env(&mut command, config);
+
+        waiting!("Running {name} for environment {}", self.environment);
+        command.check_run()
+    }
+
+    fn compose_command(&self, args: &[&str], config: &Environment) -> Command {
+        let project_name = self.project_name();
+        let mut command = self.legacy_compose_command(&["--project-name", &project_name], config);
+        command.args(args);
+        command
+    }
+
+    /// A compose command in the default project, named after the test directory, as used by earlier
+    /// versions of vdev.
+    fn legacy_compose_command(&self, args: &[&str], config: &Environment) -> Command {
         let mut command = CONTAINER_TOOL.clone();
         command.push("-compose");
         let mut command = Command::new(command);
-        command.args(["--file", &compose_file]);
+        command.args(["--file", &self.compose_file]);
         command.args(args);
+        self.apply_env(&mut command, config);
+
+        command
+    }
 
+    /// The compose project of the environment, distinct from those of the other environments of the
+    /// integration so that they can be up at the same time.
+    fn project_name(&self) -> String {
+        format!(
+            "vector-{}",
+            environment_id(&self.integration, &self.environment)
+        )
+    }
+
+    fn apply_env(&self, command: &mut Command, config: &Environment) {
         command.current_dir(&self.test_dir);
 
         command.env(NETWORK_ENV_VAR, self.runner.network_name());
+        command.envs(self.config.build_env());
         if let Some(env_vars) = &self.config.env {
             command.envs(env_vars);
         }
+        command.envs(&self.secrets);
         // TODO: Export all config variables, not just `version`
         if let Some(version) = config.get("version") {
             let version_env = format!(
@@ -178,8 +507,6 @@ impl IntegrationTest {
             );
             command.env(version_env, version);
         }
-
-        waiting!("{action} environment {}", self.environment);
-        command.check_run()
+        command.envs(&self.env_overrides);
     }
 }
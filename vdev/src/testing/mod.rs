@@ -1,4 +1,5 @@
 pub mod config;
 pub mod integration;
+pub mod results;
 pub mod runner;
 pub mod state;
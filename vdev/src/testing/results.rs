@@ -0,0 +1,293 @@
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+
+use crate::app::CommandExt as _;
+
+/// The outcome of a test run, parsed from the output of `cargo nextest`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TestSummary {
+    /// Whether the test command exited successfully.
+    pub success: bool,
+    pub run: usize,
+    pub passed: usize,
+    pub failed: usize,
+    pub skipped: usize,
+    /// The time the tests took to run, as reported by the test harness.
+    pub duration: Option<Duration>,
+    /// The names of the failed tests, in the order they failed.
+    pub failures: Vec<String>,
+}
+
+impl TestSummary {
+    /// Run the test command, printing its output as usual while parsing it into a summary.
+    pub fn run(command: &mut Command) -> Result<Self> {
+        command.pre_exec();
+        let mut child = command
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("failed to run {}", command.describe()))?;
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let stderr = child.stderr.take().expect("stderr is piped");
+
+        let summary = Mutex::new(Self::default());
+        thread::scope(|scope| {
+            let stderr = scope.spawn(|| Self::echo(stderr, io::stderr(), &summary));
+            Self::echo(stdout, io::stdout(), &summary)?;
+            stderr.join().expect("output thread panicked")
+        })?;
+
+        let mut summary = summary.into_inner().expect("output thread panicked");
+        summary.success = child.wait()?.success();
+        Ok(summary)
+    }
+
+    /// Copy each line of `input` to `output`, and parse it into the `summary`.
+    fn echo(input: impl Read, mut output: impl Write, summary: &Mutex<Self>) -> Result<()> {
+        for line in BufReader::new(input).lines() {
+            let line = line?;
+            writeln!(output, "{line}")?;
+            summary
+                .lock()
+                .expect("output thread panicked")
+                .parse_line(&line);
+        }
+        Ok(())
+    }
+
+    /// Update the summary from a line of the output of `cargo nextest`, such as
+    /// `FAIL [   0.004s] vector sinks::http::tests::retries` for a failed test, or
+    /// `Summary [  12.345s] 10 tests run: 9 passed, 1 failed, 0 skipped` at the end of the run.
+    /// Retried tests are reported with their attempt before the status, as in `TRY 2 PASS [...]`.
+    fn parse_line(&mut self, line: &str) {
+        let line = strip_ansi_codes(line);
+        let line = line.trim();
+        let line = match line.strip_prefix("TRY ") {
+            Some(retried) => retried.split_once(' ').map_or(retried, |(_, line)| line),
+            None => line,
+        };
+        let Some((status, rest)) = line.split_once(' ') else {
+            return;
+        };
+        let bracketed = rest.trim_start().strip_prefix('[');
+        let Some((elapsed, rest)) = bracketed.and_then(|rest| rest.split_once(']')) else {
+            return;
+        };
+        let rest = rest.trim();
+
+        match status {
+            "FAIL" | "TIMEOUT" | "SIGSEGV" | "SIGABRT" | "SIGKILL" => {
+                if !self.failures.iter().any(|failure| failure == rest) {
+                    self.failures.push(rest.to_owned());
+                }
+            }
+            // A test that failed an earlier attempt passed when retried.
+            "PASS" => self.failures.retain(|failure| failure != rest),
+            "Summary" => {
+                self.duration = elapsed
+                    .trim()
+                    .strip_suffix('s')
+                    .and_then(|secs| secs.parse::<f64>().ok())
+                    .map(Duration::from_secs_f64);
+                let Some((run, counts)) = rest.split_once(':') else {
+                    return;
+                };
+                self.run = leading_number(run).unwrap_or_default();
+                for count in counts.split(',') {
+                    // Counts can be qualified, as in `9 passed (1 flaky)` or `1 timed out`.
+                    let Some(number) = leading_number(count) else {
+                        continue;
+                    };
+                    match count.split_whitespace().nth(1) {
+                        Some("passed") => self.passed += number,
+                        Some("failed" | "timed") => self.failed += number,
+                        Some("skipped") => self.skipped += number,
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Fail if the test command failed, naming the failed tests if any.
+    pub fn check(&self) -> Result<()> {
+        if self.success {
+            Ok(())
+        } else if self.failures.is_empty() {
+            bail!("test command failed")
+        } else {
+            bail!("tests failed: {}", self.failures.join(", "))
+        }
+    }
+}
+
+impl fmt::Display for TestSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} run, {} passed, {} failed, {} skipped",
+            self.run, self.passed, self.failed, self.skipped
+        )?;
+        if let Some(duration) = self.duration {
+            write!(f, " in {:.1}s", duration.as_secs_f64())?;
+        }
+        Ok(())
+    }
+}
+
+fn leading_number(text: &str) -> Option<usize> {
+    text.split_whitespace().next()?.parse().ok()
+}
+
+/// Remove the escape sequences used to color the output of a terminal.
+fn strip_ansi_codes(line: &str) -> String {
+    let mut stripped = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            // Skip the sequence up to and including its final letter.
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            stripped.push(c);
+        }
+    }
+    stripped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(output: &str) -> TestSummary {
+        let mut summary = TestSummary::default();
+        for line in output.lines() {
+            summary.parse_line(line);
+        }
+        summary
+    }
+
+    #[test]
+    fn parses_passing_run() {
+        let summary = parse(
+            "    Starting 2 tests across 1 binary
+        PASS [   0.003s] vector sinks::http::tests::works
+        PASS [   0.012s] vector sinks::http::tests::batches
+------------
+     Summary [   0.015s] 2 tests run: 2 passed, 0 skipped",
+        );
+        assert_eq!(
+            summary,
+            TestSummary {
+                success: false,
+                run: 2,
+                passed: 2,
+                failed: 0,
+                skipped: 0,
+                duration: Some(Duration::from_millis(15)),
+                failures: Vec::new(),
+            }
+        );
+    }
+
+    #[test]
+    fn parses_failures_once() {
+        let summary = parse(
+            "    Starting 4 tests across 1 binary (1 skipped)
+        PASS [   0.003s] vector sinks::http::tests::works
+        FAIL [   0.004s] vector sinks::http::tests::retries
+
+--- STDOUT:              vector sinks::http::tests::retries ---
+
+running 1 test
+test sinks::http::tests::retries ... FAILED
+
+     TIMEOUT [  60.002s] vector sinks::http::tests::hangs
+------------
+     Summary [  60.010s] 3 tests run: 1 passed, 1 failed, 1 timed out, 1 skipped
+        FAIL [   0.004s] vector sinks::http::tests::retries
+     TIMEOUT [  60.002s] vector sinks::http::tests::hangs
+error: test run failed",
+        );
+        assert_eq!((summary.run, summary.passed), (3, 1));
+        assert_eq!((summary.failed, summary.skipped), (2, 1));
+        assert_eq!(
+            summary.failures,
+            [
+                "vector sinks::http::tests::retries",
+                "vector sinks::http::tests::hangs"
+            ]
+        );
+        assert_eq!(
+            summary.to_string(),
+            "3 run, 1 passed, 2 failed, 1 skipped in 60.0s"
+        );
+    }
+
+    #[test]
+    fn parses_retries() {
+        let summary = parse(
+            "    Starting 2 tests across 1 binary
+   TRY 1 FAIL [   0.004s] vector sinks::http::tests::flaky
+  RETRY 2/3 [         ] vector sinks::http::tests::flaky
+   TRY 2 PASS [   0.003s] vector sinks::http::tests::flaky
+   TRY 1 FAIL [   0.004s] vector sinks::http::tests::broken
+  RETRY 2/3 [         ] vector sinks::http::tests::broken
+   TRY 2 FAIL [   0.004s] vector sinks::http::tests::broken
+  RETRY 3/3 [         ] vector sinks::http::tests::broken
+   TRY 3 FAIL [   0.004s] vector sinks::http::tests::broken
+------------
+     Summary [   0.020s] 2 tests run: 1 passed (1 flaky), 1 failed, 0 skipped
+   FLAKY 2/3 [   0.003s] vector sinks::http::tests::flaky
+   TRY 3 FAIL [   0.004s] vector sinks::http::tests::broken",
+        );
+        assert_eq!((summary.run, summary.passed, summary.failed), (2, 1, 1));
+        assert_eq!(summary.failures, ["vector sinks::http::tests::broken"]);
+    }
+
+    #[test]
+    fn parses_colored_output() {
+        let summary = parse(
+            "\u{1b}[32;1m        PASS\u{1b}[0m [   0.003s] \u{1b}[35;1mvector\u{1b}[0m \u{1b}[34;1msinks::http::tests::\u{1b}[0m\u{1b}[36;1mworks\u{1b}[0m
+\u{1b}[31;1m        FAIL\u{1b}[0m [   0.004s] \u{1b}[35;1mvector\u{1b}[0m \u{1b}[34;1msinks::http::tests::\u{1b}[0m\u{1b}[36;1mretries\u{1b}[0m
+\u{1b}[31;1m   TRY 2 FAIL\u{1b}[0m [   0.004s] \u{1b}[35;1mvector\u{1b}[0m \u{1b}[36;1mbroken\u{1b}[0m
+------------
+\u{1b}[33;1m     Summary\u{1b}[0m [   0.007s] \u{1b}[1m3\u{1b}[0m tests run: \u{1b}[1m1\u{1b}[0m \u{1b}[32;1mpassed\u{1b}[0m, \u{1b}[1m2\u{1b}[0m \u{1b}[31;1mfailed\u{1b}[0m, \u{1b}[1m0\u{1b}[0m \u{1b}[33;1mskipped\u{1b}[0m",
+        );
+        assert_eq!((summary.run, summary.passed, summary.failed), (3, 1, 2));
+        assert_eq!(summary.duration, Some(Duration::from_millis(7)));
+        assert_eq!(
+            summary.failures,
+            ["vector sinks::http::tests::retries", "vector broken"]
+        );
+    }
+
+    #[test]
+    fn checks_success() {
+        let mut summary = parse("        FAIL [   0.004s] vector sinks::http::tests::retries");
+        let error = summary.check().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "tests failed: vector sinks::http::tests::retries"
+        );
+
+        summary.failures.clear();
+        assert_eq!(
+            summary.check().unwrap_err().to_string(),
+            "test command failed"
+        );
+
+        summary.success = true;
+        assert!(summary.check().is_ok());
+    }
+}
@@ -2,14 +2,18 @@ use std::collections::{BTreeMap, HashSet};
 use std::process::{Command, Stdio};
 use std::{env, ffi::OsString, path::PathBuf};
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use atty::Stream;
 use once_cell::sync::Lazy;
 
 use super::config::RustToolchainConfig;
+use super::results::TestSummary;
 use crate::app::{self, CommandExt as _};
 
 pub const NETWORK_ENV_VAR: &str = "VECTOR_NETWORK";
+/// The name of an existing network to run the integration tests in, instead of creating one for
+/// each environment.
+const EXTERNAL_NETWORK_ENV_VAR: &str = "VECTOR_EXTERNAL_NETWORK";
 const MOUNT_PATH: &str = "/home/vector";
 const TARGET_PATH: &str = "/home/target";
 const VOLUME_TARGET: &str = "vector_target";
@@ -51,6 +55,20 @@ fn dockercmd<'a>(args: impl IntoIterator<Item = &'a str>) -> Command {
     command
 }
 
+/// The name that tells the docker objects of an environment apart from those of the other
+/// environments, made of the names of its integration and environment. Environment names can hold
+/// any characters, so those not allowed in the names of compose projects are replaced.
+pub fn environment_id(integration: &str, environment: &str) -> String {
+    format!("{integration}-{environment}")
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | '0'..='9' | '-' | '_' => c,
+            _ => '_',
+        })
+        .collect()
+}
+
 pub enum RunnerState {
     Running,
     Restarting,
@@ -71,7 +89,9 @@ pub fn get_agent_test_runner(container: bool) -> Result<Box<dyn TestRunner>> {
 }
 
 pub trait TestRunner {
-    fn test(&self, env_vars: &BTreeMap<String, String>, args: &[String]) -> Result<()>;
+    /// Run the tests, returning their summary. Failing tests are not an error, so that the summary
+    /// is available either way; see [`TestSummary::check`].
+    fn test(&self, env_vars: &BTreeMap<String, String>, args: &[String]) -> Result<TestSummary>;
 }
 
 pub trait ContainerTestRunner: TestRunner {
@@ -83,6 +103,21 @@ pub trait ContainerTestRunner: TestRunner {
         "host".to_string()
     }
 
+    fn volumes(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Extra `KEY=VALUE` build arguments for the image.
+    fn build_args(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Whether the image is built before a missing container is created. This is not the case if
+    /// the image was already built once for several containers.
+    fn builds_image(&self) -> bool {
+        true
+    }
+
     fn stop(&self) -> Result<()> {
         dockercmd(["stop", "--time", "0", &self.container_name()])
             .wait(format!("Stopping container {}", self.container_name()))
@@ -135,7 +170,9 @@ pub trait ContainerTestRunner: TestRunner {
                 self.start()?;
             }
             RunnerState::Missing => {
-                self.build()?;
+                if self.builds_image() {
+                    self.build()?;
+                }
                 self.ensure_volumes()?;
                 self.create()?;
                 self.start()?;
@@ -145,6 +182,33 @@ pub trait ContainerTestRunner: TestRunner {
         Ok(())
     }
 
+    /// The command to open an interactive shell in the container, in which the tests can be run by
+    /// hand. The `env_vars` are passed to the shell as `KEY=VALUE`, and the `inherited_vars` are
+    /// passed by name, so that they are read from the calling shell.
+    fn shell_command(
+        &self,
+        env_vars: &BTreeMap<String, String>,
+        inherited_vars: &[&String],
+    ) -> String {
+        let mut args = vec![
+            CONTAINER_TOOL.to_string_lossy().into_owned(),
+            "exec".to_owned(),
+            "--interactive".to_owned(),
+            "--tty".to_owned(),
+            "--env".to_owned(),
+            format!("CARGO_BUILD_TARGET_DIR={TARGET_PATH}"),
+        ];
+        for (key, value) in env_vars {
+            let var = format!("{key}={value}");
+            args.extend(["--env".to_owned(), app::mask_arg(&var).into_owned()]);
+        }
+        for key in inherited_vars {
+            args.extend(["--env".to_owned(), (*key).clone()]);
+        }
+        args.extend([self.container_name(), "/bin/bash".to_owned()]);
+        args.join(" ")
+    }
+
     fn ensure_volumes(&self) -> Result<()> {
         let mut command = dockercmd(["volume", "ls", "--format", "{{.Name}}"]);
 
@@ -180,8 +244,11 @@ pub trait ContainerTestRunner: TestRunner {
             dockerfile.to_str().unwrap(),
             "--build-arg",
             &format!("RUST_VERSION={}", self.get_rust_version()),
-            ".",
         ]);
+        for build_arg in self.build_args() {
+            command.args(["--build-arg", &build_arg]);
+        }
+        command.arg(".");
 
         waiting!("Building image {}", self.image_name());
         command.check_run()
@@ -203,7 +270,7 @@ pub trait ContainerTestRunner: TestRunner {
     }
 
     fn create(&self) -> Result<()> {
-        dockercmd([
+        let mut command = dockercmd([
             "create",
             "--name",
             &self.container_name(),
@@ -219,11 +286,12 @@ pub trait ContainerTestRunner: TestRunner {
             &format!("{VOLUME_CARGO_GIT}:/usr/local/cargo/git"),
             "--volume",
             &format!("{VOLUME_CARGO_REGISTRY}:/usr/local/cargo/registry"),
-            &self.image_name(),
-            "/bin/sleep",
-            "infinity",
-        ])
-        .wait(format!("Creating container {}", self.container_name()))
+        ]);
+        for volume in self.volumes() {
+            command.args(["--volume", &volume]);
+        }
+        command.args([&self.image_name(), "/bin/sleep", "infinity"]);
+        command.wait(format!("Creating container {}", self.container_name()))
     }
 }
 
@@ -231,7 +299,7 @@ impl<T> TestRunner for T
 where
     T: ContainerTestRunner,
 {
-    fn test(&self, env_vars: &BTreeMap<String, String>, args: &[String]) -> Result<()> {
+    fn test(&self, env_vars: &BTreeMap<String, String>, args: &[String]) -> Result<TestSummary> {
         self.verify_state()?;
 
         let mut command = dockercmd(["exec"]);
@@ -249,49 +317,144 @@ where
         command.args(TEST_COMMAND);
         command.args(args);
 
-        command.check_run()
+        TestSummary::run(&mut command)
     }
 }
 
+/// The runner of an integration's tests in one of its environments. Each environment has its own
+/// container and network, so that several environments can be tested at once, but the image is
+/// shared by all the environments of the integration. The container and network are removed when
+/// the environment is stopped.
+///
+/// If `VECTOR_EXTERNAL_NETWORK` is set, all the environments use that network instead, which must
+/// already exist. It is left as is, so that it can be shared with containers outside of vdev. The
+/// services of the environments then share their names on that network, so the environments
+/// should not be run in parallel.
 pub struct IntegrationTestRunner {
     integration: String,
+    environment: String,
+    volumes: Vec<String>,
+    build_args: Vec<String>,
+    network: EnvNetwork,
+    prebuilt: bool,
 }
 
 impl IntegrationTestRunner {
-    pub fn new(integration: String) -> Result<Self> {
-        Ok(Self { integration })
+    pub fn new(
+        integration: String,
+        environment: String,
+        volumes: Vec<String>,
+        build_args: Vec<String>,
+    ) -> Result<Self> {
+        let network = EnvNetwork::from_env(&integration, &environment);
+        Ok(Self {
+            integration,
+            environment,
+            volumes,
+            build_args,
+            network,
+            prebuilt: false,
+        })
     }
 
-    pub fn ensure_network(&self) -> Result<()> {
-        let mut command = dockercmd(["network", "ls", "--format", "{{.Name}}"]);
+    /// Don't build the image for a missing container, as it was already built.
+    pub fn set_prebuilt(&mut self) {
+        self.prebuilt = true;
+    }
 
-        if command
-            .capture_output()?
-            .lines()
-            .any(|network| network == self.network_name())
-        {
+    pub fn ensure_network(&self) -> Result<()> {
+        if network_exists(self.network.name())? {
             return Ok(());
         }
 
-        dockercmd(["network", "create", &self.network_name()]).wait("Creating network")
+        match &self.network {
+            EnvNetwork::External(network) => {
+                bail!("network {network} from ${EXTERNAL_NETWORK_ENV_VAR} does not exist")
+            }
+            EnvNetwork::Owned(network) => {
+                dockercmd(["network", "create", network]).wait("Creating network")
+            }
+        }
+    }
+
+    /// Remove the network of the environment, unless it is the external network.
+    pub fn remove_network(&self) -> Result<()> {
+        match &self.network {
+            EnvNetwork::Owned(network) if network_exists(network)? => {
+                dockercmd(["network", "rm", network]).wait("Removing network")
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+fn network_exists(name: &str) -> Result<bool> {
+    let mut command = dockercmd(["network", "ls", "--format", "{{.Name}}"]);
+    Ok(command
+        .capture_output()?
+        .lines()
+        .any(|network| network == name))
+}
+
+/// The network that the containers of an environment are attached to.
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum EnvNetwork {
+    /// The network of the environment alone, created when it is started and removed when it is
+    /// stopped.
+    Owned(String),
+    /// The existing network named by `VECTOR_EXTERNAL_NETWORK`, which is left as is.
+    External(String),
+}
+
+impl EnvNetwork {
+    fn from_env(integration: &str, environment: &str) -> Self {
+        match env::var(EXTERNAL_NETWORK_ENV_VAR) {
+            Ok(network) if !network.is_empty() => Self::External(network),
+            _ => Self::Owned(format!(
+                "vector-integration-tests-{}",
+                environment_id(integration, environment)
+            )),
+        }
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            Self::Owned(name) | Self::External(name) => name,
+        }
     }
 }
 
 impl ContainerTestRunner for IntegrationTestRunner {
     fn network_name(&self) -> String {
-        format!("vector-integration-tests-{}", self.integration)
+        self.network.name().to_owned()
+    }
+
+    fn volumes(&self) -> Vec<String> {
+        self.volumes.clone()
+    }
+
+    fn build_args(&self) -> Vec<String> {
+        self.build_args.clone()
+    }
+
+    fn builds_image(&self) -> bool {
+        !self.prebuilt
     }
 
     fn container_name(&self) -> String {
         format!(
             "vector-test-runner-{}-{}",
-            self.integration,
+            environment_id(&self.integration, &self.environment),
             self.get_rust_version()
         )
     }
 
     fn image_name(&self) -> String {
-        format!("{}:latest", self.container_name())
+        format!(
+            "vector-test-runner-{}-{}:latest",
+            self.integration,
+            self.get_rust_version()
+        )
     }
 }
 
@@ -310,7 +473,7 @@ impl ContainerTestRunner for DockerTestRunner {
 pub struct LocalTestRunner;
 
 impl TestRunner for LocalTestRunner {
-    fn test(&self, env_vars: &BTreeMap<String, String>, args: &[String]) -> Result<()> {
+    fn test(&self, env_vars: &BTreeMap<String, String>, args: &[String]) -> Result<TestSummary> {
         let mut command = Command::new(TEST_COMMAND[0]);
         command.args(&TEST_COMMAND[1..]);
         command.args(args);
@@ -319,6 +482,43 @@ impl TestRunner for LocalTestRunner {
             command.env(key, value);
         }
 
-        command.check_run()
+        TestSummary::run(&mut command)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uses_external_network_when_set() {
+        env::remove_var(EXTERNAL_NETWORK_ENV_VAR);
+        assert_eq!(
+            EnvNetwork::from_env("kafka", "2.8"),
+            EnvNetwork::Owned("vector-integration-tests-kafka-2_8".to_owned())
+        );
+
+        env::set_var(EXTERNAL_NETWORK_ENV_VAR, "");
+        assert_eq!(
+            EnvNetwork::from_env("kafka", "2.8"),
+            EnvNetwork::Owned("vector-integration-tests-kafka-2_8".to_owned())
+        );
+
+        env::set_var(EXTERNAL_NETWORK_ENV_VAR, "ci-network");
+        let network = EnvNetwork::from_env("kafka", "2.8");
+        assert_eq!(network, EnvNetwork::External("ci-network".to_owned()));
+        assert_eq!(network.name(), "ci-network");
+
+        env::remove_var(EXTERNAL_NETWORK_ENV_VAR);
+    }
+
+    #[test]
+    fn sanitizes_environment_ids() {
+        assert_eq!(environment_id("kafka", "2.8"), "kafka-2_8");
+        assert_eq!(
+            environment_id("http-client", "Default"),
+            "http-client-default"
+        );
+        assert_eq!(environment_id("nats", "v2 / tls"), "nats-v2___tls");
     }
 }
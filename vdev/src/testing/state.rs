@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 
@@ -8,6 +8,7 @@ use super::config::Environment;
 use crate::platform;
 
 const CONFIG_FILE: &str = "config.json";
+const RESULTS_FILE: &str = "results.json";
 
 pub struct EnvsDir {
     path: PathBuf,
@@ -72,6 +73,34 @@ impl EnvsDir {
         serde_json::from_str(&json).with_context(|| format!("invalid contents in {config_file:?}"))
     }
 
+    /// Read whether the last test run in each environment passed. The results are kept next to
+    /// the environment directories, so they survive stopping the environments.
+    pub fn read_results(&self) -> Result<BTreeMap<String, bool>> {
+        let results_file = self.path.join(RESULTS_FILE);
+        if !results_file.is_file() {
+            return Ok(BTreeMap::new());
+        }
+
+        let json = fs::read_to_string(&results_file)
+            .with_context(|| format!("failed to read file {results_file:?}"))?;
+        serde_json::from_str(&json).with_context(|| format!("invalid contents in {results_file:?}"))
+    }
+
+    pub fn save_result(&self, environment: &str, passed: bool) -> Result<()> {
+        let mut results = self.read_results()?;
+        results.insert(environment.to_owned(), passed);
+
+        if !self.path.is_dir() {
+            fs::create_dir_all(&self.path)
+                .with_context(|| format!("failed to create directory {:?}", self.path))?;
+        }
+
+        let results_file = self.path.join(RESULTS_FILE);
+        let json = serde_json::to_string(&results)?;
+        fs::write(&results_file, json)
+            .with_context(|| format!("failed to write file {results_file:?}"))
+    }
+
     pub fn remove(&self, environment: &str) -> Result<()> {
         let env_path = self.path.join(environment);
         if env_path.is_dir() {
@@ -1,5 +1,7 @@
 use std::process::{Command, Output};
-use std::{collections::BTreeMap, fmt::Debug, fs, io::ErrorKind, path::Path};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::{collections::BTreeMap, fmt::Debug, fs, io::ErrorKind, path::Path, thread};
 
 use anyhow::{Context as _, Result};
 use serde::Deserialize;
@@ -47,6 +49,44 @@ pub fn release_channel() -> Result<&'static str> {
     })
 }
 
+/// Parse a `KEY=VALUE` command-line argument into its parts.
+pub fn parse_env_var(arg: &str) -> Result<(String, String)> {
+    match arg.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_owned(), value.to_owned())),
+        _ => anyhow::bail!("expected `KEY=VALUE`, got {arg:?}"),
+    }
+}
+
+/// Run `task` on each of the `items`, with at most `limit` of them running at once. The results are
+/// returned in the order of the items.
+pub fn run_concurrently<T: Sync, R: Send>(
+    items: &[T],
+    limit: usize,
+    task: impl Fn(&T) -> R + Sync,
+) -> Vec<R> {
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(items.iter().map(|_| None).collect::<Vec<_>>());
+    thread::scope(|scope| {
+        for _ in 0..limit.min(items.len()) {
+            scope.spawn(|| {
+                let mut index = next.fetch_add(1, Ordering::Relaxed);
+                while let Some(item) = items.get(index) {
+                    let result = task(item);
+                    results.lock().expect("results lock is poisoned")[index] = Some(result);
+                    index = next.fetch_add(1, Ordering::Relaxed);
+                }
+            });
+        }
+    });
+
+    results
+        .into_inner()
+        .expect("results lock is poisoned")
+        .into_iter()
+        .map(|result| result.expect("every item is run"))
+        .collect()
+}
+
 pub fn exists(path: impl AsRef<Path> + Debug) -> Result<bool> {
     match fs::metadata(path.as_ref()) {
         Ok(_) => Ok(true),
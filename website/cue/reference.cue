@@ -649,7 +649,9 @@ _coercing_fields: """
 	### Types
 
 	* `bool`
+	* `bool|TRUE,FALSE` (only `TRUE` and `FALSE` are recognized, case-insensitively)
 	* `string`
+	* `url_decode` (a percent-encoded `string`, with `+` decoded as a space)
 	* `float`
 	* `integer`
 	* `date`
@@ -918,6 +918,30 @@ components: sources: internal_metrics: {
 			default_namespace: "vector"
 			tags:              _component_tags
 		}
+		sample_excluded_events_total: {
+			description:       "The total number of events passed by the `sample` transform because they matched its `exclude` condition."
+			type:              "counter"
+			default_namespace: "vector"
+			tags:              _component_tags
+		}
+		sample_no_output_windows_total: {
+			description:       "The total number of `no_output_window` intervals in which the `sample` transform discarded all sampled events."
+			type:              "counter"
+			default_namespace: "vector"
+			tags:              _component_tags
+		}
+		sample_pass_ratio: {
+			description:       "The ratio of sampled events that were passed by the `sample` transform over its configured `pass_ratio_window`."
+			type:              "gauge"
+			default_namespace: "vector"
+			tags:              _component_tags
+		}
+		sample_passed_events_total: {
+			description:       "The total number of events passed by the `sample` transform after sampling."
+			type:              "counter"
+			default_namespace: "vector"
+			tags:              _component_tags
+		}
 		send_errors_total: {
 			description:       "The total number of errors sending messages."
 			type:              "counter"
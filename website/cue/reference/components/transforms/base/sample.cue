@@ -1,11 +1,138 @@
 package metadata
 
 base: components: transforms: sample: configuration: {
+	always_keep_levels: {
+		description: """
+			The levels of events that are always passed, without being sampled.
+
+			An event is passed if the value of its `level_field` is exactly one of these levels, in
+			addition to the events passed by the `exclude` condition.
+			"""
+		required: false
+		type: array: {
+			default: []
+			items: type: string: examples: ["error", "warn"]
+		}
+	}
+	annotate_metadata: {
+		description: """
+			Whether to annotate sampled events in their metadata rather than in the `sample_rate` field.
+
+			When set, the rate is written to `%sample.rate` and `%sample.sampled` is set to `true`,
+			leaving the event itself unmodified. `multiply_existing` then reads the rate from
+			`%sample.rate` as well. The `gap_field` is still written to the event.
+			"""
+		required: false
+		type: bool: default: false
+	}
+	bucket_field: {
+		description: """
+			The name of the field in which to store the hash bucket that an event fell in.
+
+			The bucket is the hash of the event's key modulo the rate, so events passed by sampling are
+			always in bucket `0`. Events sent to the `dropped` output are annotated with their bucket as
+			well, letting downstream consumers reproduce the sampling decision. Count rated events are
+			annotated with their position within each `rate` events. Events passed by the `exclude`
+			condition are not annotated.
+
+			If left unspecified, the bucket is not recorded.
+			"""
+		required: false
+		type: string: examples: ["sample_bucket"]
+	}
+	decision_cache_size: {
+		description: """
+			The number of recently seen `key_field` values whose hash bucket is cached.
+
+			When set, events whose key value was recently sampled at the same rate reuse its bucket
+			instead of hashing the value again. This speeds up streams whose keys repeat in bursts,
+			without changing which events are passed.
+
+			Only the `sha256` hash function uses the cache, as looking up a cached bucket takes longer
+			than hashing the value with the others.
+			"""
+		required: false
+		type: uint: {}
+	}
+	deterministic: {
+		description: """
+			Whether to hash the whole event to determine if it should be passed, when `key_field` is
+			unset or missing from the event.
+
+			This makes sampling independent of the order in which events arrive, at the cost of
+			serializing each of those events. When disabled, those events are count rated.
+			"""
+		required: false
+		type: bool: default: false
+	}
+	dropped_output: {
+		description: """
+			Whether to send the events that are not sampled to the `dropped` output, rather than
+			discarding them.
+
+			The sampled events are sent to the default output as usual. If nothing consumes the
+			`dropped` output when the transform is built, its events are discarded, and counted as
+			such. This cannot be combined with `shadow_output`.
+			"""
+		required: false
+		type: bool: default: false
+	}
 	exclude: {
 		description: "A logical condition used to exclude events from sampling."
 		required:    false
 		type: condition: {}
 	}
+	gap_field: {
+		description: """
+			The name of the log field in which to store the number of events discarded since the
+			previous event was passed.
+
+			If left unspecified, the number of discarded events is not recorded.
+			"""
+		required: false
+		type: string: examples: ["sample_gap"]
+	}
+	hash_fields: {
+		description: """
+			The names of log fields whose values are hashed together to determine if an event should be
+			passed, when no key field is present on the event.
+
+			Events with the same values in these fields share a sampling decision, regardless of their
+			other fields. Missing fields are hashed as `null`. This takes precedence over
+			`deterministic`.
+			"""
+		required: false
+		type: array: {
+			default: []
+			items: type: string: examples: ["host", "message"]
+		}
+	}
+	hash_function: {
+		description: """
+			The hash function used to determine if an event should be passed, when sampling by
+			`key_field` or with `deterministic` enabled.
+
+			Changing the hash function changes which events are passed. Use a portable hash function
+			to pass the same events as another system sampling by the same key at the same rate.
+			"""
+		required: false
+		type: string: {
+			default: "seahash"
+			enum: {
+				seahash: """
+					[SeaHash][seahash], which is fast but not implemented by most other systems.
+
+					[seahash]: https://docs.rs/seahash
+					"""
+				sha256: "SHA-256, with the digest interpreted as a big-endian integer."
+				xxhash: """
+					64-bit [xxHash][xxhash] (XXH64), with a seed of 0 unless `seed` is set.
+
+					[xxhash]: https://cyan4973.github.io/xxHash/
+					"""
+			}
+		}
+	}
 	key_field: {
 		description: """
 			The name of the log field whose value will be hashed to determine if the event should be
@@ -18,14 +145,165 @@ base: components: transforms: sample: configuration: {
 		required: false
 		type: string: examples: ["message"]
 	}
+	key_field_fallback: {
+		description: """
+			The names of log fields to hash, in order, when `key_field` is unset or missing from the
+			event.
+
+			The first of these fields that is present on the event is used. Events that have none of
+			them are count rated, or hashed whole when `deterministic` is enabled.
+			"""
+		required: false
+		type: array: {
+			default: []
+			items: type: string: examples: ["trace_id", "request_id", "session_id"]
+		}
+	}
+	level_field: {
+		description: "The name of the log field holding the level of an event, used by `always_keep_levels`."
+		required:    false
+		type: string: default: "level"
+	}
+	max_rate: {
+		description: """
+			The highest rate at which events will be forwarded.
+
+			The `rate` is lowered to this value, and `sample_rate` is annotated with the lowered rate,
+			if it is higher. It must be greater than 0.
+			"""
+		required: false
+		type: uint: {}
+	}
+	min_rate: {
+		description: """
+			The lowest rate at which events will be forwarded.
+
+			The `rate` is raised to this value, and `sample_rate` is annotated with the raised rate,
+			if it is lower. It must be greater than 0.
+			"""
+		required: false
+		type: uint: {}
+	}
+	multiply_existing: {
+		description: """
+			Whether to multiply the rate by the `sample_rate` already present on an event, rather than
+			overwriting it.
+
+			This records the cumulative rate of events that pass through several `sample` transforms in
+			series. Events without a numeric `sample_rate` are annotated with `rate` as usual.
+			"""
+		required: false
+		type: bool: default: false
+	}
+	no_output_window: {
+		description: """
+			The interval after which to warn that no sampled event was passed.
+
+			When set, a warning is logged and the `sample_no_output_windows_total` counter is incremented
+			at the end of each interval in which all sampled events were discarded. Events passed by
+			the `exclude` condition are not counted.
+			"""
+		required: false
+		type: string: examples: ["5m"]
+	}
+	pass_ratio_window: {
+		description: """
+			The interval over which to measure the ratio of sampled events that were passed.
+
+			When set, the observed ratio is emitted as the `sample_pass_ratio` gauge at the end of each
+			interval. It can differ from `1/rate` when the values of `key_field` are not uniformly
+			distributed. Events passed by the `exclude` condition are not counted.
+			"""
+		required: false
+		type: string: examples: ["1m"]
+	}
+	passthrough_on_reload_gap: {
+		description: """
+			The interval after the transform is built during which all events are passed without being
+			sampled.
+
+			A transform rebuilt by a configuration reload starts counting afresh, which skews the
+			sampling of the events in flight at the time of the reload. Passing them as-is for a short
+			while avoids that discontinuity. Events passed during this interval are not annotated.
+			"""
+		required: false
+		type: string: examples: ["5s"]
+	}
 	rate: {
 		description: """
 			The rate at which events will be forwarded, expressed as `1/N`.
 
 			For example, `rate = 10` means 1 out of every 10 events will be forwarded and the rest will
-			be dropped.
+			be dropped. It must be greater than 0, unless `min_rate` is set.
 			"""
 		required: true
 		type: uint: {}
 	}
+	rate_field: {
+		description: """
+			The name of the log field holding the rate at which to sample each event, overriding `rate`.
+
+			Events whose field is missing or does not hold a positive integer are sampled at `rate`. The
+			rate read from an event is still kept within `min_rate` and `max_rate`, and is the one
+			annotated in `sample_rate`.
+			"""
+		required: false
+		type: string: examples: ["tenant.sample_rate"]
+	}
+	seed: {
+		description: """
+			A seed mixed into the hash used to determine if an event should be passed, to pass a
+			different set of events at the same rate.
+
+			If left unspecified, the global `sample_seed` option is used, if set. Events that are count
+			rated are not affected.
+			"""
+		required: false
+		type: uint: {}
+	}
+	shadow_output: {
+		description: """
+			Whether to pass all events to the default output, and send the sampled events to the
+			`sampled` output instead of discarding the rest.
+
+			This allows comparing the sampled events against the full stream before switching a
+			pipeline to sampling.
+			"""
+		required: false
+		type: bool: default: false
+	}
+	size_estimator: {
+		description: "How the size of an event is measured for `size_threshold_bytes`."
+		required:    false
+		type: string: {
+			default: "estimated_json"
+			enum: {
+				estimated_json: "An estimate of the size of the event encoded as JSON, which is cheap to compute."
+				json:           "The exact size of the event encoded as JSON, at the cost of serializing each event."
+			}
+		}
+	}
+	size_threshold_bytes: {
+		description: """
+			The size, in bytes, below which events are always passed, without being sampled.
+
+			This keeps small events while sampling the large ones, which make up most of the volume
+			sent to sinks. The size of an event is measured by `size_estimator`. Events passed for
+			their size are treated like those passed by the `exclude` condition.
+			"""
+		required: false
+		type: uint: {}
+	}
+	target_rate: {
+		description: """
+			The number of sampled events to forward per second, adapting the rate to the throughput.
+
+			When set, `rate` is only the initial rate. At the end of each second, the rate is adjusted
+			so that the throughput of that second would have been sampled down to `target_rate`,
+			within the `min_rate` and `max_rate` bounds. Events passed by the `exclude` condition are
+			not counted.
+			"""
+		required: false
+		type: uint: {}
+	}
 }
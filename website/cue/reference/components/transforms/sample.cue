@@ -32,7 +32,33 @@ components: transforms: sample: {
 		traces:  true
 	}
 
+	outputs: [
+		components._default_output,
+		{
+			name: "sampled"
+			description: """
+				When `shadow_output` is set to `true`, all events are sent to the default output,
+				and the events that pass sampling are also sent to the `sampled` output, annotated
+				with the `sample_rate`. For a transform component named `foo`, this output can be
+				accessed by specifying `foo.sampled` as the input to another component.
+				"""
+		},
+		{
+			name: "dropped"
+			description: """
+				When `dropped_output` is set to `true`, the events that are not sampled are sent to
+				the `dropped` output instead of being discarded. For a transform component named
+				`foo`, this output can be accessed by specifying `foo.dropped` as the input to
+				another component.
+				"""
+		},
+	]
+
 	telemetry: metrics: {
-		events_discarded_total: components.sources.internal_metrics.output.metrics.events_discarded_total
+		events_discarded_total:         components.sources.internal_metrics.output.metrics.events_discarded_total
+		sample_excluded_events_total:   components.sources.internal_metrics.output.metrics.sample_excluded_events_total
+		sample_no_output_windows_total: components.sources.internal_metrics.output.metrics.sample_no_output_windows_total
+		sample_pass_ratio:              components.sources.internal_metrics.output.metrics.sample_pass_ratio
+		sample_passed_events_total:     components.sources.internal_metrics.output.metrics.sample_passed_events_total
 	}
 }
@@ -90,6 +90,21 @@ configuration: {
 			}
 		}
 
+		sample_seed: {
+			common: false
+			description: """
+				The seed mixed into the hashes of all `sample` transforms that do not set their own `seed`.
+
+				Changing it shifts which events are selected by all of those transforms at once, such as to
+				run reproducible load tests against a different subset of events.
+				"""
+			required: false
+			type: uint: {
+				default: null
+				examples: [42]
+			}
+		}
+
 		enrichment_tables: {
 			common:      false
 			description: """
@@ -0,0 +1,23 @@
+package metadata
+
+remap: errors: "112": {
+	title: "Expression nested too deeply"
+
+	description: """
+		An expression in the program is nested more deeply than the compiler allows, which defaults to
+		256 levels. Each negation, group, and function argument counts as one level. The operations of
+		a chain such as `a || b || c` and the branches of an `else if` chain don't nest, so long chains
+		don't count toward the limit.
+		"""
+
+	rationale: """
+		Compiling and running deeply nested expressions consumes stack space for each level, so programs
+		nested beyond the limit are rejected when compiled rather than risking a crash at runtime. Such
+		programs are usually generated by other tools.
+		"""
+
+	resolution: """
+		Simplify the nested expression, for example by removing double negations or by splitting it
+		into several assignments to variables.
+		"""
+}
@@ -0,0 +1,32 @@
+package metadata
+
+remap: errors: "661": {
+	title: "Constant negation"
+
+	description: """
+		You've used the negation operator to negate an expression whose value is known when the program
+		is compiled. This is a warning rather than an error, reported only when the compiler enables it.
+		"""
+
+	rationale: """
+		The negation of a constant always produces the same value, so an expression such as `!false` is
+		usually a mistake or a leftover from debugging.
+		"""
+
+	resolution: """
+		Replace the negation with the value it produces, or negate the expression that was intended.
+		"""
+
+	examples: [
+		{
+			"title": "\(title)"
+			source: #"""
+				!false
+				"""#
+			diff: #"""
+				- 	!false
+				+# 	!exists(.message)
+				"""#
+		},
+	]
+}
@@ -5,6 +5,10 @@ remap: expressions: comparison: {
 	description: """
 		A _comparison_ expression compares two expressions (operands) and produces a Boolean as defined by the
 		operator.
+
+		Equality compares integers and floats by their numeric value, so `1 == 1.0` is `true`. This also applies to
+		integers and floats nested in arrays and objects, which are compared element by element. Objects are equal when
+		they have the same keys with equal values, regardless of the order of the keys.
 		"""
 	return: """
 		Returns a Boolean as defined by the operator.
@@ -45,6 +49,20 @@ remap: expressions: comparison: {
 				"""#
 			return: true
 		},
+		{
+			title: "Equal integer and float"
+			source: #"""
+				1 == 1.0
+				"""#
+			return: true
+		},
+		{
+			title: "Equal nested collections"
+			source: #"""
+				{ "values": [1, 2] } == { "values": [1.0, 2.0] }
+				"""#
+			return: true
+		},
 		{
 			title: "Not equal"
 			source: #"""